@@ -1,55 +1,845 @@
 use crate::prelude::*;
 
+use chrono::{NaiveDate, NaiveDateTime};
 use figment::{
     providers::{Format, Yaml},
     Figment,
 };
 use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::env;
 
-//name_of_the_client:
-//  url: "http://test.com/api"
-//  resources:
-//    - name: endpoint 1
-//      resource: /ep1
-//      fields_list:
-//        - name: field_1_name
-//        - name: field_2_name
-//    - name: endpoint 2
-//      resource: /ep2
-//      fields_list:
-//        - name: field_1_name
-//        - name: field_2_name
+use crate::provider::{FilterPayload, Identifier, SortOrder};
+
+//clients:
+//  - name: name_of_the_client
+//    url: "http://test.com/api" # the database file with sqlite and json_file
+//    provider: json_server | rest | postgrest | graphql | sqlite | json_file | memory
+//    http:
+//      timeout: 30
+//      user_agent: rtui2
+//      concurrency: 8
+//      headers:
+//        Authorization: Bearer token
+//    rest:
+//      endpoints:
+//        list: {path: "{resource}"}
+//        one: {path: "{resource}/{id}"}
+//        update: {method: PATCH}
+//        many_reference: {path: "{target}?parentId={id}"}
+//      pagination: offset
+//      offset_param: skip
+//      limit_param: take
+//      sort_param: sort
+//      desc_prefix: "-"
+//      filters:
+//        gte: "{field}[gte]"
+//        contains: "{field}[like]"
+//      in_separator: ","
+//      search_param: search
+//      ids_param: id
+//      total:
+//        body: meta.total
+//      records_path: data.items
+//      record_path: data
+//      id_field: _id
+//    postgrest:
+//      schema: public
+//      id_field: id
+//    sqlite:
+//      id_field: id
+//    memory:
+//      seed: ./db.json # records are generated from the fields without it
+//      records: 25
+//      latency: 300
+//      fail_every: 5
+//      fail_ids: [3]
+//    graphql:
+//      path: graphql
+//      filters:
+//        contains: "{field}_like"
+//    resources:
+//      - name: endpoint 1
+//        resource: /ep1
+//        fields:
+//          - name: field_1_name
+//          - name: field_2_name
+//            type: number
+//            min: 0
+//      - name: endpoint 2
+//        resource: /ep2
+//        per_page: 50
+//        filter:
+//          conditions:
+//            - field: field_1_name
+//              ne: b
+//        fields:
+//          - name: field_1_name
+//            type: enum
+//            choices: [a, b]
+//          - name: field_2_name
+//            type: reference
+//            reference: /ep1
+//            label: field_1_name
+//        rest:
+//          records_path: results
+//          id_field: [field_1_name, field_2_name]
+//          id_separator: ":"
+//        graphql:
+//          type_name: Entry
+//          one: |
+//            query ($id: ID!) { item: entry(id: $id) { id field_1_name } }
+//          total:
+//            header: X-Count
+//        relations:
+//          - name: children
+//            target: ep3
+//            # postgrest and sqlite name the foreign key column, as in
+//            # ep3.ep2_id, sqlite finds it from the schema otherwise
+//            per_page: 10
+//            sort: field_1_name
+//            order: desc
+//            filter:
+//              q: text
+//              conditions:
+//                - field: field_1_name
+//                  gte: 10
+//            fields:
+//              - name: field_1_name
+//  - name: another_client
+//    provider: memory
+//    resources: []
+//
+// A file holding a single client may describe it at its top level,
+// without `clients`.
 //
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    #[default]
+    String,
+    Number,
+    Bool,
+    Date,
+    Enum,
+    Reference,
+    Json,
+}
+
+// Format used for date fields when none is configured.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct Field {
     pub name: String,
-    // TODO: type
+    #[serde(default, rename = "type")]
+    pub kind: FieldType,
+    // Allowed values of an enum field.
+    #[serde(default)]
+    pub choices: Vec<String>,
+    // chrono format string of a date field.
+    pub format: Option<String>,
+    // Bounds of a number field.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    // Resource a reference field points to, and the field of the referenced
+    // record displayed instead of the raw id.
+    pub reference: Option<String>,
+    pub label: Option<String>,
+}
+
+impl Field {
+    pub fn date_format(&self) -> &str {
+        self.format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT)
+    }
+
+    // Converts user input into the JSON value the API expects for this field.
+    // Empty input maps to null for every type but strings.
+    pub fn coerce(&self, input: &str) -> Result<Value> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(match self.kind {
+                FieldType::String => Value::String(String::new()),
+                _ => Value::Null,
+            });
+        }
+
+        match self.kind {
+            FieldType::String => Ok(Value::String(input.to_string())),
+            FieldType::Number => self.coerce_number(input),
+            FieldType::Bool => match input.to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "n" | "0" => Ok(Value::Bool(false)),
+                _ => Err(self.invalid("expected true or false")),
+            },
+            FieldType::Date => {
+                let format = self.date_format();
+                let valid = NaiveDate::parse_from_str(input, format).is_ok()
+                    || NaiveDateTime::parse_from_str(input, format).is_ok();
+                if !valid {
+                    return Err(self.invalid(&format!("expected a date like {}", format)));
+                }
+                Ok(Value::String(input.to_string()))
+            }
+            FieldType::Enum => {
+                if !self.choices.iter().any(|c| c == input) {
+                    let choices = self.choices.join(", ");
+                    return Err(self.invalid(&format!("expected one of {}", choices)));
+                }
+                Ok(Value::String(input.to_string()))
+            }
+            FieldType::Reference => Ok(match input.parse::<u64>() {
+                Ok(n) => Value::from(n),
+                Err(_) => Value::String(input.to_string()),
+            }),
+            FieldType::Json => {
+                serde_json::from_str(input).map_err(|e| self.invalid(&e.to_string()))
+            }
+        }
+    }
+
+    fn coerce_number(&self, input: &str) -> Result<Value> {
+        let number: f64 = input
+            .parse()
+            .map_err(|_| self.invalid("expected a number"))?;
+
+        if let Some(min) = self.min {
+            if number < min {
+                return Err(self.invalid(&format!("must be at least {}", min)));
+            }
+        }
+        if let Some(max) = self.max {
+            if number > max {
+                return Err(self.invalid(&format!("must be at most {}", max)));
+            }
+        }
+
+        if let Ok(n) = input.parse::<i64>() {
+            return Ok(Value::from(n));
+        }
+        serde_json::Number::from_f64(number)
+            .map(Value::Number)
+            .ok_or_else(|| self.invalid("expected a finite number"))
+    }
+
+    // Renders a value back into the text a user would type for this field.
+    pub fn to_input(&self, value: &Value) -> String {
+        match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    fn invalid(&self, message: &str) -> Error {
+        Error::InvalidField {
+            field: self.name.clone(),
+            message: message.to_string(),
+        }
+    }
+}
+
+// Default page sizes of resource lists and related records.
+pub const DEFAULT_LIST_PER_PAGE: usize = 25;
+pub const DEFAULT_PER_PAGE: usize = 10;
+
+fn default_list_per_page() -> usize {
+    DEFAULT_LIST_PER_PAGE
+}
+
+fn default_per_page() -> usize {
+    DEFAULT_PER_PAGE
+}
+
+// Child records reachable from a record through `get_many_reference`.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Relation {
+    pub name: String,
+    pub target: String,
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub order: SortOrder,
+    #[serde(default)]
+    pub filter: FilterPayload,
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct Resource {
     pub name: String,
     pub resource: String,
     pub fields: Vec<Field>,
+    #[serde(default = "default_list_per_page")]
+    pub per_page: usize,
+    // Filter applied when the resource is opened.
+    #[serde(default)]
+    pub filter: FilterPayload,
+    #[serde(default)]
+    pub relations: Vec<Relation>,
+    // Overrides of the client `rest` and `graphql` settings for this
+    // resource.
+    pub rest: Option<RestConfig>,
+    pub graphql: Option<GraphqlConfig>,
 }
 
-#[derive(Debug, Default, Deserialize)]
+// Settings of the HTTP client shared by every request of a provider.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct HttpConfig {
+    // Timeouts in seconds.
+    pub timeout: Option<u64>,
+    pub connect_timeout: Option<u64>,
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    // Requests in flight at once during bulk operations.
+    pub concurrency: Option<usize>,
+}
+
+// Bulk operations run this many requests at once when not configured.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+impl HttpConfig {
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1)
+    }
+}
+
+// HTTP verb and path template of one operation. `{resource}`, `{id}` and
+// `{target}` are replaced in the path, `{id.<field>}` by one part of a
+// composite id.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Endpoint {
+    pub method: Option<String>,
+    pub path: Option<String>,
+}
+
+impl Endpoint {
+    fn merge(&self, other: &Endpoint) -> Endpoint {
+        Endpoint {
+            method: other.method.clone().or_else(|| self.method.clone()),
+            path: other.path.clone().or_else(|| self.path.clone()),
+        }
+    }
+
+    pub fn method(&self, default: &str) -> String {
+        self.method.as_deref().unwrap_or(default).to_uppercase()
+    }
+
+    pub fn path(&self, default: &str) -> String {
+        self.path.as_deref().unwrap_or(default).to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Endpoints {
+    #[serde(default)]
+    pub list: Endpoint,
+    #[serde(default)]
+    pub one: Endpoint,
+    #[serde(default)]
+    pub many: Endpoint,
+    #[serde(default)]
+    pub many_reference: Endpoint,
+    #[serde(default)]
+    pub create: Endpoint,
+    #[serde(default)]
+    pub update: Endpoint,
+    #[serde(default)]
+    pub delete: Endpoint,
+}
+
+impl Endpoints {
+    fn merge(&self, other: &Endpoints) -> Endpoints {
+        Endpoints {
+            list: self.list.merge(&other.list),
+            one: self.one.merge(&other.one),
+            many: self.many.merge(&other.many),
+            many_reference: self.many_reference.merge(&other.many_reference),
+            create: self.create.merge(&other.create),
+            update: self.update.merge(&other.update),
+            delete: self.delete.merge(&other.delete),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaginationStyle {
+    // `page` and `per_page` parameters, pages counted from 1.
+    #[default]
+    Page,
+    // `offset` and `limit` parameters, offsets counted from 0.
+    Offset,
+    // The API returns everything at once.
+    None,
+}
+
+// Where the size of the whole collection is found in a list response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TotalLocation {
+    Header(String),
+    Body(String),
+}
+
+// Field, or fields of a composite key, holding the id of a record.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum IdField {
+    Single(String),
+    Composite(Vec<String>),
+}
+
+// Separator of the parts of composite ids when none is configured.
+pub const DEFAULT_ID_SEPARATOR: &str = ":";
+
+// Conventions of a REST API, read by `RestProvider`. Every setting is
+// optional so a resource only overrides what differs from its client.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RestConfig {
+    #[serde(default)]
+    pub endpoints: Endpoints,
+    pub pagination: Option<PaginationStyle>,
+    pub page_param: Option<String>,
+    pub per_page_param: Option<String>,
+    pub offset_param: Option<String>,
+    pub limit_param: Option<String>,
+    pub sort_param: Option<String>,
+    // Parameter carrying the order. Without it descending sorts prefix the
+    // field with `desc_prefix`, as in `sort=-name`.
+    pub order_param: Option<String>,
+    pub desc_prefix: Option<String>,
+    // Parameter name templates by filter operator, `{field}` is replaced.
+    #[serde(default)]
+    pub filters: HashMap<String, String>,
+    // Joins the operands of `in` filters in one parameter instead of
+    // repeating it.
+    pub in_separator: Option<String>,
+    pub search_param: Option<String>,
+    // Parameter used to fetch several records by id.
+    pub ids_param: Option<String>,
+    pub total: Option<TotalLocation>,
+    // Dotted path to the records array of a list response, e.g. `data.items`.
+    pub records_path: Option<String>,
+    // Dotted path to the record in the response of a single record.
+    pub record_path: Option<String>,
+    pub id_field: Option<IdField>,
+    pub id_separator: Option<String>,
+}
+
+impl RestConfig {
+    // Settings of `other` win over the ones of `self`.
+    pub fn merge(&self, other: &RestConfig) -> RestConfig {
+        let mut filters = self.filters.clone();
+        filters.extend(other.filters.clone());
+
+        RestConfig {
+            endpoints: self.endpoints.merge(&other.endpoints),
+            pagination: other.pagination.or(self.pagination),
+            page_param: other.page_param.clone().or_else(|| self.page_param.clone()),
+            per_page_param: other
+                .per_page_param
+                .clone()
+                .or_else(|| self.per_page_param.clone()),
+            offset_param: other
+                .offset_param
+                .clone()
+                .or_else(|| self.offset_param.clone()),
+            limit_param: other
+                .limit_param
+                .clone()
+                .or_else(|| self.limit_param.clone()),
+            sort_param: other.sort_param.clone().or_else(|| self.sort_param.clone()),
+            order_param: other
+                .order_param
+                .clone()
+                .or_else(|| self.order_param.clone()),
+            desc_prefix: other
+                .desc_prefix
+                .clone()
+                .or_else(|| self.desc_prefix.clone()),
+            filters,
+            in_separator: other
+                .in_separator
+                .clone()
+                .or_else(|| self.in_separator.clone()),
+            search_param: other
+                .search_param
+                .clone()
+                .or_else(|| self.search_param.clone()),
+            ids_param: other.ids_param.clone().or_else(|| self.ids_param.clone()),
+            total: other.total.clone().or_else(|| self.total.clone()),
+            records_path: other
+                .records_path
+                .clone()
+                .or_else(|| self.records_path.clone()),
+            record_path: other
+                .record_path
+                .clone()
+                .or_else(|| self.record_path.clone()),
+            id_field: other.id_field.clone().or_else(|| self.id_field.clone()),
+            id_separator: other
+                .id_separator
+                .clone()
+                .or_else(|| self.id_separator.clone()),
+        }
+    }
+
+    pub fn id_fields(&self) -> Vec<String> {
+        match &self.id_field {
+            Some(IdField::Single(field)) => vec![field.clone()],
+            Some(IdField::Composite(fields)) => fields.clone(),
+            None => vec!["id".to_string()],
+        }
+    }
+
+    pub fn id_separator(&self) -> &str {
+        self.id_separator.as_deref().unwrap_or(DEFAULT_ID_SEPARATOR)
+    }
+
+    // Name of the query parameter of filter operator `op` on `field`. The
+    // defaults follow json-server, which has no strict comparison: `gt` and
+    // `lt` have no parameter unless one is configured.
+    pub fn filter_param(&self, op: &str, field: &str) -> Option<String> {
+        let template = match self.filters.get(op) {
+            Some(template) => template.as_str(),
+            None => match op {
+                "eq" | "in" => "{field}",
+                "ne" => "{field}_ne",
+                "gte" => "{field}_gte",
+                "lte" => "{field}_lte",
+                "contains" => "{field}_like",
+                _ => return None,
+            },
+        };
+        Some(template.replace("{field}", field))
+    }
+
+    pub fn total(&self) -> TotalLocation {
+        self.total
+            .clone()
+            .unwrap_or_else(|| TotalLocation::Header("X-Total-Count".to_string()))
+    }
+}
+
+// Settings of `PostgrestProvider`.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct PostgrestConfig {
+    // Schema sent in the `Accept-Profile` and `Content-Profile` headers,
+    // the first exposed schema is used otherwise.
+    pub schema: Option<String>,
+    // Primary key column of the tables.
+    pub id_field: Option<String>,
+}
+
+// Settings of `GraphqlProvider`. Operations are generated from the fields
+// of a resource unless a query is templated, and every setting but `path`
+// can be overridden per resource.
+//
+// Templates receive their arguments as variables and alias their results
+// `items` and `total` for lists, `item` for single records:
+//   $page (from 0), $perPage, $sortField, $sortOrder, $filter, $id, $data
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct GraphqlConfig {
+    // Endpoint, relative to the client url.
+    pub path: Option<String>,
+    // Singular type name of generated operations, e.g. `Post` for
+    // `allPosts`, `createPost` and so on.
+    pub type_name: Option<String>,
+    // Filter keys by operator, `{field}` is replaced.
+    #[serde(default)]
+    pub filters: HashMap<String, String>,
+    pub list: Option<String>,
+    pub one: Option<String>,
+    pub create: Option<String>,
+    pub update: Option<String>,
+    pub delete: Option<String>,
+}
+
+impl GraphqlConfig {
+    // Settings of `other` win over the ones of `self`.
+    pub fn merge(&self, other: &GraphqlConfig) -> GraphqlConfig {
+        let mut filters = self.filters.clone();
+        filters.extend(other.filters.clone());
+
+        GraphqlConfig {
+            path: other.path.clone().or_else(|| self.path.clone()),
+            type_name: other.type_name.clone().or_else(|| self.type_name.clone()),
+            filters,
+            list: other.list.clone().or_else(|| self.list.clone()),
+            one: other.one.clone().or_else(|| self.one.clone()),
+            create: other.create.clone().or_else(|| self.create.clone()),
+            update: other.update.clone().or_else(|| self.update.clone()),
+            delete: other.delete.clone().or_else(|| self.delete.clone()),
+        }
+    }
+
+    // Key of filter operator `op` on `field`. The defaults follow
+    // json-graphql-server, which has no `contains` nor `in` on a field, so
+    // those are only sent when configured.
+    pub fn filter_key(&self, op: &str, field: &str) -> Option<String> {
+        let template = match self.filters.get(op) {
+            Some(template) => template.as_str(),
+            None => match op {
+                "eq" => "{field}",
+                "ne" => "{field}_neq",
+                "gt" => "{field}_gt",
+                "gte" => "{field}_gte",
+                "lt" => "{field}_lt",
+                "lte" => "{field}_lte",
+                _ => return None,
+            },
+        };
+        Some(template.replace("{field}", field))
+    }
+}
+
+// Settings of `SqliteProvider`, whose database file is the client `url`.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct SqliteConfig {
+    // Primary key column of the tables.
+    pub id_field: Option<String>,
+}
+
+// Settings of the in-memory provider, which serves generated records, or
+// the collections of a `db.json` seed, without any network.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct MemoryConfig {
+    // Path to a `db.json` to start from instead of generated records.
+    pub seed: Option<String>,
+    // Records generated for each resource and relation.
+    pub records: Option<usize>,
+    // Delay of every call in milliseconds.
+    pub latency: Option<u64>,
+    // Every nth call fails.
+    pub fail_every: Option<usize>,
+    // Calls touching these records fail.
+    #[serde(default)]
+    pub fail_ids: Vec<Identifier>,
+}
+
+// Records generated for each collection when none is configured.
+pub const DEFAULT_MEMORY_RECORDS: usize = 25;
+
+impl MemoryConfig {
+    pub fn records(&self) -> usize {
+        self.records.unwrap_or(DEFAULT_MEMORY_RECORDS)
+    }
+}
+
+// Kind of API a client talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    JsonServer,
+    Rest,
+    Postgrest,
+    Graphql,
+    Sqlite,
+    JsonFile,
+    Memory,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct Config {
     pub name: String,
+    // Unused by the memory provider.
+    #[serde(default)]
     pub url: String,
+    // Defaults to `rest` when a `rest` section is present, to `json_server`
+    // otherwise.
+    pub provider: Option<ProviderKind>,
+    #[serde(default)]
+    pub http: HttpConfig,
+    // Conventions of the API, served by `RestProvider`.
+    pub rest: Option<RestConfig>,
+    pub postgrest: Option<PostgrestConfig>,
+    pub graphql: Option<GraphqlConfig>,
+    pub sqlite: Option<SqliteConfig>,
+    pub memory: Option<MemoryConfig>,
     pub resources: Vec<Resource>,
 }
 
 impl Config {
+    pub fn provider(&self) -> ProviderKind {
+        match (self.provider, &self.rest) {
+            (Some(kind), _) => kind,
+            (None, Some(_)) => ProviderKind::Rest,
+            (None, None) => ProviderKind::JsonServer,
+        }
+    }
+
+    pub fn from_file(file_path: &str) -> Result<Self> {
+        Ok(Figment::new()
+            .merge(Yaml::file(file_path))
+            .extract()
+            .map_err(Box::new)?)
+    }
+}
+
+// Clients holds every client of a config file, in the order they appear.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Clients {
+    pub clients: Vec<Config>,
+}
+
+impl Clients {
     pub fn from_cli() -> Result<Self> {
         let args: Vec<String> = env::args().collect();
 
         let file_path = &args[1];
-        Config::from_file(file_path)
+        Clients::from_file(file_path)
     }
 
+    // Reads the `clients` of the file, or the single client described at
+    // its top level.
     pub fn from_file(file_path: &str) -> Result<Self> {
-        Ok(Figment::new().merge(Yaml::file(file_path)).extract()?)
+        let figment = Figment::new().merge(Yaml::file(file_path));
+        let clients: Clients = if figment.contains("clients") {
+            figment.extract().map_err(Box::new)?
+        } else {
+            Clients {
+                clients: vec![figment.extract().map_err(Box::new)?],
+            }
+        };
+
+        if clients.clients.is_empty() {
+            return Err(config_error("no client configured".to_string()));
+        }
+        for (i, client) in clients.clients.iter().enumerate() {
+            if clients.clients[..i].iter().any(|c| c.name == client.name) {
+                return Err(config_error(format!("duplicate client {}", client.name)));
+            }
+        }
+        Ok(clients)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Config> {
+        self.clients.iter().find(|c| c.name == name)
+    }
+}
+
+fn config_error(message: String) -> Error {
+    Error::Config(Box::new(figment::Error::from(message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field(kind: FieldType) -> Field {
+        Field {
+            name: "field".to_string(),
+            kind,
+            ..Field::default()
+        }
+    }
+
+    fn rejects(field: &Field, input: &str) -> String {
+        match field.coerce(input) {
+            Err(Error::InvalidField { field, message }) => format!("{}: {}", field, message),
+            other => panic!("expected {:?} to be rejected, got {:?}", input, other.ok()),
+        }
+    }
+
+    #[test]
+    fn empty_input_is_null_but_for_strings() {
+        assert_eq!(field(FieldType::String).coerce("  ").unwrap(), json!(""));
+        for kind in [
+            FieldType::Number,
+            FieldType::Bool,
+            FieldType::Date,
+            FieldType::Enum,
+            FieldType::Reference,
+            FieldType::Json,
+        ] {
+            assert_eq!(field(kind).coerce("").unwrap(), Value::Null);
+        }
+    }
+
+    #[test]
+    fn numbers_keep_integers_apart_from_floats() {
+        let number = field(FieldType::Number);
+        assert_eq!(number.coerce(" 42 ").unwrap(), json!(42));
+        assert!(number.coerce("42").unwrap().is_i64());
+        assert_eq!(number.coerce("-1.5").unwrap(), json!(-1.5));
+        assert!(number.coerce("2.0").unwrap().is_f64());
+        assert_eq!(rejects(&number, "forty"), "field: expected a number");
+        assert_eq!(rejects(&number, "inf"), "field: expected a finite number");
+    }
+
+    #[test]
+    fn numbers_stay_within_their_bounds() {
+        let number = Field {
+            min: Some(0.0),
+            max: Some(10.0),
+            ..field(FieldType::Number)
+        };
+        assert_eq!(number.coerce("0").unwrap(), json!(0));
+        assert_eq!(number.coerce("10").unwrap(), json!(10));
+        assert_eq!(rejects(&number, "-0.5"), "field: must be at least 0");
+        assert_eq!(rejects(&number, "11"), "field: must be at most 10");
+    }
+
+    #[test]
+    fn bools_accept_several_spellings() {
+        let bool = field(FieldType::Bool);
+        for input in ["true", "Yes", "y", "1"] {
+            assert_eq!(bool.coerce(input).unwrap(), json!(true), "{}", input);
+        }
+        for input in ["FALSE", "no", "n", "0"] {
+            assert_eq!(bool.coerce(input).unwrap(), json!(false), "{}", input);
+        }
+        assert_eq!(rejects(&bool, "maybe"), "field: expected true or false");
+    }
+
+    #[test]
+    fn dates_follow_their_format() {
+        let date = field(FieldType::Date);
+        assert_eq!(date.coerce("2024-02-29").unwrap(), json!("2024-02-29"));
+        assert_eq!(
+            rejects(&date, "2023-02-29"),
+            "field: expected a date like %Y-%m-%d"
+        );
+
+        let datetime = Field {
+            format: Some("%d/%m/%Y %H:%M".to_string()),
+            ..field(FieldType::Date)
+        };
+        assert_eq!(
+            datetime.coerce("31/12/2024 23:59").unwrap(),
+            json!("31/12/2024 23:59")
+        );
+        rejects(&datetime, "2024-12-31");
+    }
+
+    #[test]
+    fn enums_take_one_of_their_choices() {
+        let status = Field {
+            choices: vec!["draft".to_string(), "published".to_string()],
+            ..field(FieldType::Enum)
+        };
+        assert_eq!(status.coerce("draft").unwrap(), json!("draft"));
+        assert_eq!(
+            rejects(&status, "Draft"),
+            "field: expected one of draft, published"
+        );
+    }
+
+    #[test]
+    fn references_and_json_are_parsed() {
+        let reference = field(FieldType::Reference);
+        assert_eq!(reference.coerce("7").unwrap(), json!(7));
+        assert_eq!(reference.coerce("abc").unwrap(), json!("abc"));
+
+        let data = field(FieldType::Json);
+        assert_eq!(data.coerce(r#"{"a": [1]}"#).unwrap(), json!({"a": [1]}));
+        rejects(&data, "{");
     }
 }
@@ -1,4 +1,9 @@
+use std::collections::HashMap;
 use std::error;
+use std::fmt::{self, Display};
+
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -11,15 +16,185 @@ pub enum Error {
     #[error("failed to make the request {0}")]
     Request(#[from] reqwest::Error),
 
-    #[error("{0}")]
-    RequestStatus(String),
+    #[error(transparent)]
+    Http(Box<HttpError>),
 
     #[error("{0}")]
     Parse(#[from] serde_json::error::Error),
 
     #[error(transparent)]
-    Config(#[from] figment::Error),
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("{}", .0.iter().map(|e| e.message.as_str()).collect::<Vec<_>>().join("; "))]
+    Graphql(Vec<GraphqlError>),
+
+    #[error("no record {id} in {resource}")]
+    NotFound { resource: String, id: String },
+
+    #[error("unexpected response: {0}")]
+    Response(String),
+
+    #[error("invalid value for {field}: {message}")]
+    InvalidField { field: String, message: String },
+
+    #[error(transparent)]
+    Config(#[from] Box<figment::Error>),
 
     #[error("unknown  error {0}")]
     Unknown(String),
 }
+
+impl Error {
+    // Status code of a failed HTTP request, to tell a 404 from a 409.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Http(e) => Some(e.status),
+            Error::Request(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    // Validation errors by field name, from the API or from input coercion.
+    pub fn field_errors(&self) -> HashMap<String, String> {
+        match self {
+            Error::Http(e) => e.field_errors.clone(),
+            Error::InvalidField { field, message } => {
+                HashMap::from([(field.clone(), message.clone())])
+            }
+            // e.g. "NOT NULL constraint failed: posts.title"
+            Error::Sqlite(rusqlite::Error::SqliteFailure(e, Some(message)))
+                if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                let column = message.rsplit_once('.').map(|(_, column)| column);
+                column
+                    .map(|c| HashMap::from([(c.to_string(), message.clone())]))
+                    .unwrap_or_default()
+            }
+            Error::Graphql(errors) => errors
+                .iter()
+                .filter_map(|e| Some((e.field()?.to_string(), e.message.clone())))
+                .collect(),
+            _ => HashMap::new(),
+        }
+    }
+}
+
+impl From<HttpError> for Error {
+    fn from(e: HttpError) -> Self {
+        Error::Http(Box::new(e))
+    }
+}
+
+// HttpError holds a non-2xx response: the request that caused it and the
+// parsed body, including field-level validation errors when the API sends
+// them (typically on 400 and 422).
+#[derive(Debug, Clone)]
+pub struct HttpError {
+    pub status: StatusCode,
+    pub method: Method,
+    pub url: String,
+    // JSON body, or the raw text as a JSON string when it is not JSON.
+    pub body: Option<serde_json::Value>,
+    pub field_errors: HashMap<String, String>,
+}
+
+impl HttpError {
+    pub fn new(status: StatusCode, method: Method, url: &str, body: &str) -> Self {
+        let body = match body.trim() {
+            "" => None,
+            text => Some(
+                serde_json::from_str(text)
+                    .unwrap_or_else(|_| serde_json::Value::String(text.to_string())),
+            ),
+        };
+        let field_errors = body.as_ref().map(parse_field_errors).unwrap_or_default();
+
+        Self {
+            status,
+            method,
+            url: url.to_string(),
+            body,
+            field_errors,
+        }
+    }
+
+    // Human readable message of the body, if the API sent one.
+    pub fn message(&self) -> Option<String> {
+        match self.body.as_ref()? {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Object(map) => ["message", "error", "detail", "title"]
+                .iter()
+                .find_map(|k| map.get(*k).and_then(|v| v.as_str()))
+                .map(String::from),
+            _ => None,
+        }
+    }
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.status)?;
+        if let Some(message) = self.message() {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for HttpError {}
+
+// GraphqlError is one entry of the `errors` array of a GraphQL response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphqlError {
+    pub message: String,
+    // Response field the error belongs to, e.g. the alias of a mutation.
+    #[serde(default)]
+    pub path: Vec<serde_json::Value>,
+    pub extensions: Option<serde_json::Value>,
+}
+
+impl GraphqlError {
+    // Input field rejected by validation, when the server names it.
+    pub fn field(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("field")?.as_str()
+    }
+}
+
+// Understands the usual shapes of validation errors:
+//   {"errors": {"title": ["can't be blank"]}}
+//   {"errors": {"title": "can't be blank"}}
+//   {"errors": [{"field": "title", "message": "can't be blank"}]}
+fn parse_field_errors(body: &serde_json::Value) -> HashMap<String, String> {
+    let mut errors = HashMap::new();
+    match body.get("errors") {
+        Some(serde_json::Value::Object(map)) => {
+            for (field, value) in map {
+                let message = match value {
+                    serde_json::Value::Array(items) => items
+                        .iter()
+                        .map(|i| i.as_str().map(String::from).unwrap_or(i.to_string()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                errors.insert(field.clone(), message);
+            }
+        }
+        Some(serde_json::Value::Array(items)) => {
+            for item in items {
+                let field = ["field", "path", "name"]
+                    .iter()
+                    .find_map(|k| item.get(*k).and_then(|v| v.as_str()));
+                let message = ["message", "msg", "error"]
+                    .iter()
+                    .find_map(|k| item.get(*k).and_then(|v| v.as_str()));
+                if let (Some(field), Some(message)) = (field, message) {
+                    errors.insert(field.to_string(), message.to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+    errors
+}
@@ -0,0 +1,9 @@
+#![allow(unused)]
+
+pub mod config;
+pub mod error;
+pub mod prelude;
+pub mod provider;
+pub mod providers;
+pub mod tui;
+pub mod utils;
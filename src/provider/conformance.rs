@@ -0,0 +1,712 @@
+use crate::prelude::*;
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use crate::provider::*;
+
+// Conformance kit: runs the same CRUD, pagination, sorting, filter and
+// reference scenarios against any `DataProvider` and reports the semantics
+// it violates, so every provider answers to one contract.
+//
+// The provider must serve two empty collections: `resource`, whose records
+// get `title` (string), `views` (number) and `published` (bool) fields, and
+// `target`, whose records point at them through `foreign_key`.
+#[derive(Debug, Clone)]
+pub struct Suite {
+    pub resource: String,
+    pub target: String,
+    pub foreign_key: String,
+    // Target passed to `get_many_reference`, `target` when unset.
+    pub reference: Option<String>,
+}
+
+impl Suite {
+    pub fn new(resource: &str, target: &str, foreign_key: &str) -> Self {
+        Self {
+            resource: resource.to_string(),
+            target: target.to_string(),
+            foreign_key: foreign_key.to_string(),
+            reference: None,
+        }
+    }
+
+    pub fn with_reference(mut self, reference: &str) -> Self {
+        self.reference = Some(reference.to_string());
+        self
+    }
+
+    // Runs every scenario in order. Scenarios build on the records left by
+    // the previous ones, so the kit stops at the first failed setup.
+    pub async fn run(&self, provider: &(impl DataProvider + ?Sized)) -> Report {
+        let mut report = Report::default();
+        let mut ids = match create(self, provider).await {
+            Ok(ids) => {
+                report.passed.push("create");
+                ids
+            }
+            Err(message) => {
+                report.fail("create", message);
+                return report;
+            }
+        };
+
+        report.check("get_one", get_one(self, provider, &ids).await);
+        report.check("get_one missing", get_one_missing(self, provider).await);
+        report.check("get_many", get_many(self, provider, &ids).await);
+        report.check("pagination", pagination(self, provider, &ids).await);
+        report.check("sort", sort(self, provider).await);
+        report.check("filter", filter(self, provider).await);
+        report.check("search", search(self, provider).await);
+        report.check("update", update(self, provider, &ids).await);
+        report.check("update_many", update_many(self, provider, &ids).await);
+        report.check("reference", reference(self, provider, &ids).await);
+        report.check("delete", delete(self, provider, &mut ids).await);
+        report.check("delete_many", delete_many(self, provider, &ids).await);
+        report
+    }
+
+    fn resource(&self) -> Resource {
+        Resource::new(&self.resource)
+    }
+}
+
+// Report struct, the scenarios that passed and those that did not
+#[derive(Debug, Default)]
+pub struct Report {
+    pub passed: Vec<&'static str>,
+    pub violations: Vec<Violation>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    fn check(&mut self, scenario: &'static str, outcome: std::result::Result<(), String>) {
+        match outcome {
+            Ok(()) => self.passed.push(scenario),
+            Err(message) => self.fail(scenario, message),
+        }
+    }
+
+    fn fail(&mut self, scenario: &'static str, message: String) {
+        self.violations.push(Violation { scenario, message });
+    }
+}
+
+// Implement `Display` for `Report`, one line per violation
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} passed, {} violated",
+            self.passed.len(),
+            self.violations.len()
+        )?;
+        for violation in &self.violations {
+            write!(f, "\n  {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+// Violation struct, a scenario and what the provider did wrong
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub scenario: &'static str,
+    pub message: String,
+}
+
+// Implement `Display` for `Violation`
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.scenario, self.message)
+    }
+}
+
+type Outcome<T = ()> = std::result::Result<T, String>;
+
+// Records created by the kit, in the order of their ids.
+const FIXTURES: [(&str, i64, bool); 5] = [
+    ("alpha", 50, false),
+    ("bravo", 10, false),
+    ("charlie", 40, true),
+    ("delta", 20, false),
+    ("echo", 30, true),
+];
+
+fn ensure(condition: bool, message: impl FnOnce() -> String) -> Outcome {
+    if condition {
+        Ok(())
+    } else {
+        Err(message())
+    }
+}
+
+fn failed(call: &str) -> impl FnOnce(Error) -> String + '_ {
+    move |e| format!("{} failed: {}", call, e)
+}
+
+// Values match across JSON types as long as they mean the same, e.g. a
+// number read back as a numeric string.
+fn same(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn field<'a>(record: &'a Record, name: &str) -> &'a Value {
+    record.fields.get(name).unwrap_or(&Value::Null)
+}
+
+fn titles(records: &[Record]) -> Vec<String> {
+    records
+        .iter()
+        .map(|r| match field(r, "title") {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect()
+}
+
+fn id_value(id: &Identifier) -> Value {
+    match id {
+        Identifier::Num(n) => json!(n),
+        Identifier::Str(s) => json!(s),
+    }
+}
+
+fn fixture(title: &str, views: i64, published: bool) -> HashMap<String, Value> {
+    HashMap::from([
+        ("title".to_string(), json!(title)),
+        ("views".to_string(), json!(views)),
+        ("published".to_string(), json!(published)),
+    ])
+}
+
+fn expect_fields(record: &Record, data: &HashMap<String, Value>) -> Outcome {
+    for (name, expected) in data {
+        let actual = field(record, name);
+        ensure(same(actual, expected), || {
+            format!(
+                "record {} has {} = {}, expected {}",
+                record.id, name, actual, expected
+            )
+        })?;
+    }
+    Ok(())
+}
+
+async fn list(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    filter: Option<FilterPayload>,
+    sort: Option<(&str, SortOrder)>,
+    pagination: Option<(usize, usize)>,
+) -> Outcome<GetListResult> {
+    let params = GetListParams {
+        pagination: pagination.map(|(page, per_page)| PaginationPayload { page, per_page }),
+        sort: sort.map(|(field, order)| SortPayload {
+            field: field.to_string(),
+            order,
+        }),
+        filter,
+        meta: None,
+    };
+    provider
+        .get_list(suite.resource(), params)
+        .await
+        .map_err(failed("get_list"))
+}
+
+async fn create(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+) -> Outcome<Vec<Identifier>> {
+    let mut ids = Vec::new();
+    for (title, views, published) in FIXTURES {
+        let data = fixture(title, views, published);
+        let params = CreateParams {
+            data: data.clone(),
+            meta: None,
+        };
+        let created = provider
+            .create(suite.resource(), params)
+            .await
+            .map_err(failed("create"))?;
+        expect_fields(&created.data, &data)?;
+        ensure(!ids.contains(&created.data.id), || {
+            format!("id {} given to two records", created.data.id)
+        })?;
+        ids.push(created.data.id);
+    }
+    Ok(ids)
+}
+
+async fn get_one(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &[Identifier],
+) -> Outcome {
+    for (id, (title, views, published)) in ids.iter().zip(FIXTURES) {
+        let params = GetOneParams {
+            id: id.clone(),
+            meta: None,
+        };
+        let one = provider
+            .get_one(suite.resource(), params)
+            .await
+            .map_err(failed("get_one"))?;
+        ensure(&one.data.id == id, || {
+            format!("asked for {}, got {}", id, one.data.id)
+        })?;
+        expect_fields(&one.data, &fixture(title, views, published))?;
+    }
+    Ok(())
+}
+
+async fn get_one_missing(suite: &Suite, provider: &(impl DataProvider + ?Sized)) -> Outcome {
+    let params = GetOneParams {
+        id: Identifier::Num(999_999),
+        meta: None,
+    };
+    let one = provider.get_one(suite.resource(), params).await;
+    ensure(one.is_err(), || {
+        "a missing record is not an error".to_string()
+    })
+}
+
+async fn get_many(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &[Identifier],
+) -> Outcome {
+    let wanted = vec![ids[0].clone(), ids[2].clone()];
+    let params = GetManyParams {
+        ids: wanted.clone(),
+        meta: None,
+    };
+    let many = provider
+        .get_many(suite.resource(), params)
+        .await
+        .map_err(failed("get_many"))?;
+    let mut got: Vec<String> = many.data.iter().map(|r| r.id.to_string()).collect();
+    got.sort();
+    let mut wanted: Vec<String> = wanted.iter().map(Identifier::to_string).collect();
+    wanted.sort();
+    ensure(got == wanted, || {
+        format!("asked for {:?}, got {:?}", wanted, got)
+    })
+}
+
+async fn pagination(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &[Identifier],
+) -> Outcome {
+    let mut seen = Vec::new();
+    for (page, size, next) in [(1, 2, true), (2, 2, true), (3, 1, false)] {
+        let result = list(
+            suite,
+            provider,
+            None,
+            Some(("views", SortOrder::Asc)),
+            Some((page, 2)),
+        )
+        .await?;
+        ensure(result.data.len() == size, || {
+            format!(
+                "page {} has {} records, expected {}",
+                page,
+                result.data.len(),
+                size
+            )
+        })?;
+        ensure(result.total == Some(FIXTURES.len()), || {
+            format!(
+                "page {} reports a total of {:?}, expected 5",
+                page, result.total
+            )
+        })?;
+        if let Some(info) = result.page_info {
+            ensure(info.has_next_page == next, || {
+                format!("page {} has_next_page is {}", page, info.has_next_page)
+            })?;
+            ensure(info.has_previous_page == (page > 1), || {
+                format!(
+                    "page {} has_previous_page is {}",
+                    page, info.has_previous_page
+                )
+            })?;
+        }
+        seen.extend(result.data.into_iter().map(|r| r.id));
+    }
+
+    let mut seen: Vec<String> = seen.iter().map(Identifier::to_string).collect();
+    seen.sort();
+    seen.dedup();
+    ensure(seen.len() == ids.len(), || {
+        format!(
+            "pages hold {} distinct records, expected {}",
+            seen.len(),
+            ids.len()
+        )
+    })
+}
+
+async fn sort(suite: &Suite, provider: &(impl DataProvider + ?Sized)) -> Outcome {
+    let cases = [
+        (
+            "views",
+            SortOrder::Asc,
+            ["bravo", "delta", "echo", "charlie", "alpha"],
+        ),
+        (
+            "views",
+            SortOrder::Desc,
+            ["alpha", "charlie", "echo", "delta", "bravo"],
+        ),
+        (
+            "title",
+            SortOrder::Asc,
+            ["alpha", "bravo", "charlie", "delta", "echo"],
+        ),
+        (
+            "title",
+            SortOrder::Desc,
+            ["echo", "delta", "charlie", "bravo", "alpha"],
+        ),
+    ];
+    for (field, order, expected) in cases {
+        let result = list(suite, provider, None, Some((field, order)), None).await?;
+        let got = titles(&result.data);
+        ensure(got == expected, || {
+            format!(
+                "sorted by {} {}: {:?}, expected {:?}",
+                field, order, got, expected
+            )
+        })?;
+    }
+    Ok(())
+}
+
+async fn filter(suite: &Suite, provider: &(impl DataProvider + ?Sized)) -> Outcome {
+    let cases = vec![
+        (Filter::Eq(json!("charlie")), "title", vec!["charlie"]),
+        (
+            Filter::Ne(json!("charlie")),
+            "title",
+            vec!["bravo", "delta", "echo", "alpha"],
+        ),
+        (Filter::Gt(json!(30)), "views", vec!["charlie", "alpha"]),
+        (
+            Filter::Gte(json!(30)),
+            "views",
+            vec!["echo", "charlie", "alpha"],
+        ),
+        (Filter::Lt(json!(20)), "views", vec!["bravo"]),
+        (Filter::Lte(json!(20)), "views", vec!["bravo", "delta"]),
+        (
+            Filter::Range(json!(20), json!(40)),
+            "views",
+            vec!["delta", "echo", "charlie"],
+        ),
+        (
+            Filter::Contains("HAR".to_string()),
+            "title",
+            vec!["charlie"],
+        ),
+        (
+            Filter::In(vec![json!("alpha"), json!("echo")]),
+            "title",
+            vec!["echo", "alpha"],
+        ),
+        (
+            Filter::Eq(json!(true)),
+            "published",
+            vec!["echo", "charlie"],
+        ),
+    ];
+    for (operator, name, expected) in cases {
+        let payload = FilterPayload::new().with(name, operator);
+        let description = payload.conditions[0].to_string();
+        let result = list(
+            suite,
+            provider,
+            Some(payload),
+            Some(("views", SortOrder::Asc)),
+            None,
+        )
+        .await?;
+        let got = titles(&result.data);
+        ensure(got == expected, || {
+            format!("{} matched {:?}, expected {:?}", description, got, expected)
+        })?;
+        ensure(result.total == Some(expected.len()), || {
+            format!(
+                "{} reports a total of {:?}, expected {}",
+                description,
+                result.total,
+                expected.len()
+            )
+        })?;
+    }
+    Ok(())
+}
+
+async fn search(suite: &Suite, provider: &(impl DataProvider + ?Sized)) -> Outcome {
+    let payload = FilterPayload::new().search("ELT");
+    let result = list(suite, provider, Some(payload), None, None).await?;
+    let got = titles(&result.data);
+    ensure(got == ["delta"], || {
+        format!("searching \"ELT\" matched {:?}, expected [\"delta\"]", got)
+    })
+}
+
+async fn update(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &[Identifier],
+) -> Outcome {
+    let (_, views, published) = FIXTURES[1];
+    let data = fixture("bravo 2", views, published);
+    let previous = provider
+        .get_one(
+            suite.resource(),
+            GetOneParams {
+                id: ids[1].clone(),
+                meta: None,
+            },
+        )
+        .await
+        .map_err(failed("get_one"))?;
+    let params = UpdateParams {
+        id: ids[1].clone(),
+        data: data.clone(),
+        previous_data: previous.data,
+        meta: None,
+    };
+    let updated = provider
+        .update(suite.resource(), params)
+        .await
+        .map_err(failed("update"))?;
+    ensure(updated.data.id == ids[1], || {
+        format!("updating {} returned {}", ids[1], updated.data.id)
+    })?;
+    expect_fields(&updated.data, &data)?;
+
+    let one = provider
+        .get_one(
+            suite.resource(),
+            GetOneParams {
+                id: ids[1].clone(),
+                meta: None,
+            },
+        )
+        .await
+        .map_err(failed("get_one"))?;
+    expect_fields(&one.data, &data).map_err(|e| format!("update not kept, {}", e))
+}
+
+async fn update_many(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &[Identifier],
+) -> Outcome {
+    let wanted = vec![ids[3].clone(), ids[4].clone()];
+    let data = HashMap::from([("views".to_string(), json!(99))]);
+    let params = UpdateManyParams {
+        ids: wanted.clone(),
+        data: data.clone(),
+        meta: None,
+    };
+    let result = provider
+        .update_many(suite.resource(), params)
+        .await
+        .map_err(failed("update_many"))?;
+    ensure(result.data.len() == wanted.len(), || {
+        format!("{} outcomes for {} ids", result.data.len(), wanted.len())
+    })?;
+    ensure(result.data.iter().all(ItemOutcome::is_ok), || {
+        summarize(&result.data, "updated")
+    })?;
+
+    let params = GetManyParams {
+        ids: wanted,
+        meta: None,
+    };
+    let many = provider
+        .get_many(suite.resource(), params)
+        .await
+        .map_err(failed("get_many"))?;
+    for record in &many.data {
+        expect_fields(record, &data).map_err(|e| format!("update not kept, {}", e))?;
+    }
+    Ok(())
+}
+
+async fn reference(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &[Identifier],
+) -> Outcome {
+    let target = Resource::new(&suite.target);
+    let mut created = Vec::new();
+    for (n, parent) in [&ids[0], &ids[0], &ids[0], &ids[1]].into_iter().enumerate() {
+        let data = HashMap::from([
+            ("body".to_string(), json!(format!("comment {}", n))),
+            (suite.foreign_key.clone(), id_value(parent)),
+        ]);
+        let params = CreateParams { data, meta: None };
+        let record = provider
+            .create(target.clone(), params)
+            .await
+            .map_err(failed("create"))?;
+        created.push(record.data.id);
+    }
+
+    let params = GetManyReferenceParams {
+        target: suite.reference.clone().unwrap_or(suite.target.clone()),
+        id: ids[0].clone(),
+        pagination: PaginationPayload {
+            page: 1,
+            per_page: 2,
+        },
+        sort: SortPayload {
+            field: "body".to_string(),
+            order: SortOrder::Desc,
+        },
+        filter: FilterPayload::new(),
+        meta: None,
+    };
+    let result = provider
+        .get_many_reference(suite.resource(), params)
+        .await
+        .map_err(failed("get_many_reference"))?;
+
+    let bodies: Vec<&Value> = result.data.iter().map(|r| field(r, "body")).collect();
+    ensure(bodies == [&json!("comment 2"), &json!("comment 1")], || {
+        format!(
+            "first page of references sorted by body desc is {:?}",
+            bodies
+        )
+    })?;
+    for record in &result.data {
+        ensure(
+            same(field(record, &suite.foreign_key), &id_value(&ids[0])),
+            || format!("reference {} does not point at {}", record.id, ids[0]),
+        )?;
+    }
+    ensure(result.total == Some(3), || {
+        format!(
+            "references report a total of {:?}, expected 3",
+            result.total
+        )
+    })?;
+
+    // Removed so that deleting their parents later breaks no constraint.
+    let params = DeleteManyParams {
+        ids: created,
+        meta: None,
+    };
+    let result = provider
+        .delete_many(target, params)
+        .await
+        .map_err(failed("delete_many"))?;
+    ensure(result.data.iter().all(ItemOutcome::is_ok), || {
+        summarize(&result.data, "deleted")
+    })
+}
+
+async fn delete(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &mut Vec<Identifier>,
+) -> Outcome {
+    let id = ids[2].clone();
+    let previous = provider
+        .get_one(
+            suite.resource(),
+            GetOneParams {
+                id: id.clone(),
+                meta: None,
+            },
+        )
+        .await
+        .map_err(failed("get_one"))?;
+    let params = DeleteParams {
+        id: id.clone(),
+        previous_data: Some(previous.data),
+        meta: None,
+    };
+    let deleted = provider
+        .delete(suite.resource(), params)
+        .await
+        .map_err(failed("delete"))?;
+    ensure(deleted.data.id == id, || {
+        format!("deleting {} returned {}", id, deleted.data.id)
+    })?;
+    ids.remove(2);
+
+    let one = provider
+        .get_one(
+            suite.resource(),
+            GetOneParams {
+                id: id.clone(),
+                meta: None,
+            },
+        )
+        .await;
+    ensure(one.is_err(), || {
+        format!("{} is still there once deleted", id)
+    })?;
+
+    let result = list(suite, provider, None, None, None).await?;
+    ensure(result.total == Some(ids.len()), || {
+        format!(
+            "{:?} records left after a delete, expected {}",
+            result.total,
+            ids.len()
+        )
+    })
+}
+
+async fn delete_many(
+    suite: &Suite,
+    provider: &(impl DataProvider + ?Sized),
+    ids: &[Identifier],
+) -> Outcome {
+    let params = DeleteManyParams {
+        ids: ids.to_vec(),
+        meta: None,
+    };
+    let result = provider
+        .delete_many(suite.resource(), params)
+        .await
+        .map_err(failed("delete_many"))?;
+    ensure(result.data.len() == ids.len(), || {
+        format!("{} outcomes for {} ids", result.data.len(), ids.len())
+    })?;
+    ensure(result.data.iter().all(ItemOutcome::is_ok), || {
+        summarize(&result.data, "deleted")
+    })?;
+
+    let result = list(suite, provider, None, None, None).await?;
+    ensure(result.data.is_empty() && result.total == Some(0), || {
+        format!("{} records left after deleting them all", result.data.len())
+    })
+}
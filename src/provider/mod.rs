@@ -1,13 +1,14 @@
 use crate::prelude::*;
 
+use async_trait::async_trait;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fmt::{self, Display};
-use std::future::Future;
-use std::pin::Pin;
+
+pub mod conformance;
 
 // Identifier type can be either a String or a u64 (number in Rust).
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
 #[serde(untagged)] // Allow deserialization of both strings and numbers
 pub enum Identifier {
     Str(String),
@@ -48,19 +49,82 @@ impl From<Identifier> for String {
     }
 }
 
+// Implement `TryFrom<&serde_json::Value>` for `Identifier`, used to follow
+// foreign keys stored in record fields.
+impl TryFrom<&serde_json::Value> for Identifier {
+    type Error = Error;
+    fn try_from(value: &serde_json::Value) -> Result<Self> {
+        match value {
+            serde_json::Value::String(s) => Ok(Identifier::Str(s.clone())),
+            serde_json::Value::Number(n) => n
+                .as_u64()
+                .map(|n| Identifier::Num(n as usize))
+                .ok_or_else(|| Error::Unknown(format!("Invalid identifier {}", n))),
+            other => Err(Error::Unknown(format!("Invalid identifier {}", other))),
+        }
+    }
+}
+
 // RaRecord struct with generic IdentifierType
 pub trait RaRecord {
     type IdentifierType;
     fn id(&self) -> &Self::IdentifierType;
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Record {
     pub id: Identifier,
     #[serde(flatten)]
     pub fields: HashMap<String, serde_json::Value>,
 }
 
+impl Record {
+    // Builds a record out of a JSON object whose id is stored in `id_fields`.
+    // Composite ids join their parts with `separator`.
+    pub fn from_json(
+        value: serde_json::Value,
+        id_fields: &[String],
+        separator: &str,
+    ) -> Result<Record> {
+        let serde_json::Value::Object(mut fields) = value else {
+            return Err(Error::Response(format!("expected a record, got {}", value)));
+        };
+
+        let parts = id_fields
+            .iter()
+            .map(|f| {
+                fields
+                    .get(f)
+                    .ok_or_else(|| Error::Response(format!("record without `{}` field", f)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let id = match parts.as_slice() {
+            [single] => Identifier::try_from(*single)?,
+            parts => Identifier::Str(
+                parts
+                    .iter()
+                    .map(|v| match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(separator),
+            ),
+        };
+
+        // A single id field is not repeated among the fields, as when
+        // deserialized. The parts of composite ids are data of their own.
+        if let [field] = id_fields {
+            fields.remove(field);
+        }
+
+        Ok(Record {
+            id,
+            fields: fields.into_iter().collect(),
+        })
+    }
+}
+
 impl RaRecord for Record {
     type IdentifierType = Identifier;
     fn id(&self) -> &Self::IdentifierType {
@@ -69,18 +133,124 @@ impl RaRecord for Record {
 }
 
 // SortPayload struct
+#[derive(Debug, Clone)]
 pub struct SortPayload {
     pub field: String,
     pub order: SortOrder,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SortOrder {
+    #[default]
     Asc,
     Desc,
 }
 
-// FilterPayload as a HashMap
-pub type FilterPayload = HashMap<String, String>;
+impl SortOrder {
+    pub fn toggle(self) -> Self {
+        match self {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        }
+    }
+}
+
+// Implement `Display` for `SortOrder`
+impl Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortOrder::Asc => write!(f, "asc"),
+            SortOrder::Desc => write!(f, "desc"),
+        }
+    }
+}
+
+// Filter operator and operand(s) applied to a field
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    Eq(serde_json::Value),
+    Ne(serde_json::Value),
+    Gt(serde_json::Value),
+    Gte(serde_json::Value),
+    Lt(serde_json::Value),
+    Lte(serde_json::Value),
+    // Inclusive on both ends
+    Range(serde_json::Value, serde_json::Value),
+    // Case-insensitive substring match
+    Contains(String),
+    In(Vec<serde_json::Value>),
+}
+
+// FilterCondition struct
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FilterCondition {
+    pub field: String,
+    #[serde(flatten)]
+    pub filter: Filter,
+}
+
+// FilterPayload struct, every condition must hold and `q` is a full-text
+// search over the whole record.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct FilterPayload {
+    #[serde(default)]
+    pub conditions: Vec<FilterCondition>,
+    pub q: Option<String>,
+}
+
+impl FilterPayload {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, field: &str, filter: Filter) -> Self {
+        self.conditions.push(FilterCondition {
+            field: field.to_string(),
+            filter,
+        });
+        self
+    }
+
+    pub fn search(mut self, q: &str) -> Self {
+        self.q = Some(q.to_string());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty() && self.q.is_none()
+    }
+
+    // Adds the conditions of `other`, its search replacing the current one.
+    pub fn merge(&mut self, other: FilterPayload) {
+        self.conditions.extend(other.conditions);
+        if other.q.is_some() {
+            self.q = other.q;
+        }
+    }
+}
+
+// Implement `Display` for `FilterCondition`
+impl Display for FilterCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = &self.field;
+        match &self.filter {
+            Filter::Eq(v) => write!(f, "{} = {}", field, v),
+            Filter::Ne(v) => write!(f, "{} != {}", field, v),
+            Filter::Gt(v) => write!(f, "{} > {}", field, v),
+            Filter::Gte(v) => write!(f, "{} >= {}", field, v),
+            Filter::Lt(v) => write!(f, "{} < {}", field, v),
+            Filter::Lte(v) => write!(f, "{} <= {}", field, v),
+            Filter::Range(from, to) => write!(f, "{} in {}..{}", field, from, to),
+            Filter::Contains(v) => write!(f, "{} ~ {}", field, v),
+            Filter::In(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{} in [{}]", field, values.join(", "))
+            }
+        }
+    }
+}
 
 // Meta as a HashMap
 pub type Meta = HashMap<String, serde_json::Value>;
@@ -102,67 +272,82 @@ impl Resource {
     }
 }
 
+// Singular of an English resource name, used to guess foreign keys and type
+// names: `posts` gives `post`, `categories` gives `category` and `addresses`
+// gives `address`, while `status` and `class` are kept as they are.
+pub fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        return format!("{}y", stem);
+    }
+    for suffix in ["sses", "xes", "ches", "shes"] {
+        if name.ends_with(suffix) {
+            return name[..name.len() - 2].to_string();
+        }
+    }
+    if name.ends_with("ss") || name.ends_with("us") {
+        return name.to_string();
+    }
+    name.strip_suffix('s').unwrap_or(name).to_string()
+}
+
+// Plural of an English type name, the reverse of `singular`: `Entry` gives
+// `Entries`, `Address` gives `Addresses` and `Post` gives `Posts`.
+pub fn plural(name: &str) -> String {
+    let consonant_y = name
+        .strip_suffix('y')
+        .filter(|stem| !stem.ends_with(['a', 'e', 'i', 'o', 'u']));
+    if let Some(stem) = consonant_y {
+        return format!("{}ies", stem);
+    }
+    if ["s", "x", "ch", "sh"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+    {
+        return format!("{}es", name);
+    }
+    format!("{}s", name)
+}
+
 // PaginationPayload struct
+#[derive(Debug, Clone, Copy)]
 pub struct PaginationPayload {
     pub page: usize,
     pub per_page: usize,
 }
 
-// DataProvider trait
-pub trait DataProvider {
-    fn get_list(
-        &self,
-        resource: Resource,
-        params: GetListParams,
-    ) -> Pin<Box<dyn Future<Output = Result<GetListResult>> + '_>>;
+// DataProvider trait. Its futures are `Send`, so a provider shared as
+// `Arc<dyn DataProvider>` can serve calls spawned on any worker thread.
+#[async_trait]
+pub trait DataProvider: Send + Sync {
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult>;
 
-    fn get_one(
-        &self,
-        resource: Resource,
-        params: GetOneParams,
-    ) -> Pin<Box<dyn Future<Output = Result<GetOneResult>> + '_>>;
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult>;
 
-    fn get_many(
-        &self,
-        resource: Resource,
-        params: GetManyParams,
-    ) -> Pin<Box<dyn Future<Output = Result<GetManyResult>> + '_>>;
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult>;
 
-    fn get_many_reference(
+    async fn get_many_reference(
         &self,
         resource: Resource,
         params: GetManyReferenceParams,
-    ) -> Pin<Box<dyn Future<Output = Result<GetManyReferenceResult>> + '_>>;
+    ) -> Result<GetManyReferenceResult>;
 
-    fn update(
-        &self,
-        resource: Resource,
-        params: UpdateParams,
-    ) -> Pin<Box<dyn Future<Output = Result<UpdateResult>> + '_>>;
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult>;
 
-    fn update_many(
+    async fn update_many(
         &self,
         resource: Resource,
         params: UpdateManyParams,
-    ) -> Pin<Box<dyn Future<Output = Result<UpdateManyResult>> + '_>>;
+    ) -> Result<UpdateManyResult>;
 
-    fn create(
-        &self,
-        resource: Resource,
-        params: CreateParams,
-    ) -> Pin<Box<dyn Future<Output = Result<CreateResult>> + '_>>;
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult>;
 
-    fn delete(
-        &self,
-        resource: Resource,
-        params: DeleteParams,
-    ) -> Pin<Box<dyn Future<Output = Result<DeleteResult>> + '_>>;
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult>;
 
-    fn delete_many(
+    async fn delete_many(
         &self,
         resource: Resource,
         params: DeleteManyParams,
-    ) -> Pin<Box<dyn Future<Output = Result<DeleteManyResult>> + '_>>;
+    ) -> Result<DeleteManyResult>;
 }
 
 // GetListParams struct
@@ -241,9 +426,9 @@ pub struct UpdateManyParams {
     pub meta: Option<Meta>,
 }
 
-// UpdateManyResult struct
+// UpdateManyResult struct, one outcome per requested id
 pub struct UpdateManyResult {
-    pub data: Vec<Identifier>,
+    pub data: Vec<ItemOutcome>,
 }
 
 // CreateParams struct
@@ -275,13 +460,71 @@ pub struct DeleteManyParams {
     pub meta: Option<Meta>,
 }
 
-// DeleteManyResult struct
+// DeleteManyResult struct, one outcome per requested id
 pub struct DeleteManyResult {
-    pub data: Vec<Identifier>,
+    pub data: Vec<ItemOutcome>,
+}
+
+// ItemOutcome struct, the result of a bulk operation for a single id
+#[derive(Debug)]
+pub struct ItemOutcome {
+    pub id: Identifier,
+    pub result: Result<()>,
+}
+
+impl ItemOutcome {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+// Ids whose operation failed, e.g. to retry them.
+pub fn failed_ids(outcomes: &[ItemOutcome]) -> Vec<Identifier> {
+    outcomes
+        .iter()
+        .filter(|o| !o.is_ok())
+        .map(|o| o.id.clone())
+        .collect()
+}
+
+// Summarizes a bulk operation, e.g. "48 deleted, 2 failed: 403 Forbidden".
+pub fn summarize(outcomes: &[ItemOutcome], verb: &str) -> String {
+    let succeeded = outcomes.iter().filter(|o| o.is_ok()).count();
+    let mut errors: Vec<String> = Vec::new();
+    for outcome in outcomes {
+        if let Err(e) = &outcome.result {
+            let e = e.to_string();
+            if !errors.contains(&e) {
+                errors.push(e);
+            }
+        }
+    }
+
+    let failed = outcomes.len() - succeeded;
+    if failed == 0 {
+        return format!("{} {}", succeeded, verb);
+    }
+    format!(
+        "{} {}, {} failed: {}",
+        succeeded,
+        verb,
+        failed,
+        errors.join("; ")
+    )
 }
 
 // PageInfo struct
+#[derive(Debug, Clone, Copy)]
 pub struct PageInfo {
     pub has_next_page: bool,
     pub has_previous_page: bool,
 }
+
+impl PageInfo {
+    pub fn new(pagination: &PaginationPayload, total: usize) -> Self {
+        Self {
+            has_next_page: pagination.page * pagination.per_page < total,
+            has_previous_page: pagination.page > 1,
+        }
+    }
+}
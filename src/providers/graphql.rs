@@ -0,0 +1,480 @@
+use crate::prelude::*;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use url::Url;
+
+use crate::config::{self, Config, GraphqlConfig};
+use crate::error::{GraphqlError, HttpError};
+use crate::provider::*;
+use crate::providers::http::build_client;
+
+// GraphqlProvider maps the operations of the trait to GraphQL queries and
+// mutations, named after json-graphql-server: `allPosts`, `_allPostsMeta`,
+// `Post`, `createPost`, `updatePost` and `deletePost`.
+pub struct GraphqlProvider {
+    url: Url,
+    client: reqwest::Client,
+    // Requests in flight at once during bulk operations.
+    concurrency: usize,
+    // Client settings, used by resources without overrides.
+    graphql: GraphqlConfig,
+    // Client settings merged with the overrides of each resource.
+    resources: HashMap<String, GraphqlConfig>,
+    // Configured fields of each resource, selected by generated queries.
+    fields: HashMap<String, Vec<config::Field>>,
+}
+
+// Operations on one resource.
+struct Operations<'a> {
+    config: &'a GraphqlConfig,
+    type_name: String,
+    plural: String,
+    // Selection set of the records, e.g. `{ id title }`.
+    selection: String,
+}
+
+impl GraphqlProvider {
+    pub fn new(config: &Config) -> Result<Self> {
+        let graphql = config.graphql.clone().unwrap_or_default();
+        let url = Url::parse(&config.url)?;
+        let url = match &graphql.path {
+            Some(path) => url.join(path)?,
+            None => url,
+        };
+
+        Ok(Self {
+            url,
+            client: build_client(&config.http)?,
+            concurrency: config.http.concurrency(),
+            resources: config
+                .resources
+                .iter()
+                .filter_map(|r| {
+                    let overrides = r.graphql.as_ref()?;
+                    Some((
+                        Resource::new(&r.resource).resource,
+                        graphql.merge(overrides),
+                    ))
+                })
+                .collect(),
+            graphql,
+            fields: config
+                .resources
+                .iter()
+                .map(|r| (Resource::new(&r.resource).resource, r.fields.clone()))
+                .collect(),
+        })
+    }
+
+    fn operations(&self, resource: &str) -> Operations<'_> {
+        let config = self.resources.get(resource).unwrap_or(&self.graphql);
+        // json-graphql-server names the lists after the resource and the
+        // type after its singular.
+        let (type_name, plural) = match &config.type_name {
+            Some(type_name) => (type_name.clone(), plural(type_name)),
+            None => (capitalize(&singular(resource)), capitalize(resource)),
+        };
+        let fields = self.fields.get(resource).into_iter().flatten();
+        let selection = std::iter::once("id")
+            .chain(fields.map(|f| f.name.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Operations {
+            config,
+            type_name,
+            plural,
+            selection: format!("{{ {} }}", selection),
+        }
+    }
+
+    // Posts a query and returns its `data`, mapping the `errors` array, when
+    // there is one, into the crate error.
+    async fn execute(&self, query: &str, variables: Value) -> Result<Value> {
+        let request = self
+            .client
+            .post(self.url.clone())
+            .json(&json!({ "query": query, "variables": variables }))
+            .build()?;
+        let response = self.client.execute(request).await?;
+        let status = response.status();
+        let text = response.text().await?;
+        let body: Option<Value> = serde_json::from_str(&text).ok();
+
+        if let Some(errors) = body.as_ref().and_then(|b| b.get("errors")) {
+            let errors: Vec<GraphqlError> = serde_json::from_value(errors.clone())?;
+            if !errors.is_empty() {
+                return Err(Error::Graphql(errors));
+            }
+        }
+        if !status.is_success() {
+            let error = HttpError::new(status, reqwest::Method::POST, self.url.as_str(), &text);
+            return Err(error.into());
+        }
+
+        match body.and_then(|mut b| b.get_mut("data").map(Value::take)) {
+            Some(data) if !data.is_null() => Ok(data),
+            _ => Err(Error::Response(
+                "no data in the GraphQL response".to_string(),
+            )),
+        }
+    }
+
+    // Fails on operators the server has no key for, instead of dropping
+    // them and listing more than was asked.
+    fn filter(
+        &self,
+        config: &GraphqlConfig,
+        filters: &FilterPayload,
+    ) -> Result<Map<String, Value>> {
+        let mut filter = Map::new();
+        for condition in &filters.conditions {
+            let field = &condition.field;
+            let mut set = |op: &str, value: Value| match config.filter_key(op, field) {
+                Some(key) => {
+                    filter.insert(key, value);
+                    Ok(())
+                }
+                None => Err(Error::InvalidField {
+                    field: field.clone(),
+                    message: format!("the `{}` filter is not supported", op),
+                }),
+            };
+            match &condition.filter {
+                Filter::Eq(v) => set("eq", v.clone())?,
+                Filter::Ne(v) => set("ne", v.clone())?,
+                Filter::Gt(v) => set("gt", v.clone())?,
+                Filter::Gte(v) => set("gte", v.clone())?,
+                Filter::Lt(v) => set("lt", v.clone())?,
+                Filter::Lte(v) => set("lte", v.clone())?,
+                Filter::Range(from, to) => {
+                    set("gte", from.clone())?;
+                    set("lte", to.clone())?;
+                }
+                Filter::Contains(v) => set("contains", Value::String(v.clone()))?,
+                Filter::In(values) => set("in", Value::Array(values.clone()))?,
+            }
+        }
+        if let Some(q) = &filters.q {
+            filter.insert("q".to_string(), Value::String(q.clone()));
+        }
+        Ok(filter)
+    }
+
+    async fn fetch_list(
+        &self,
+        ops: &Operations<'_>,
+        pagination: Option<&PaginationPayload>,
+        sort: Option<&SortPayload>,
+        filter: Map<String, Value>,
+    ) -> Result<(Vec<Record>, Option<usize>)> {
+        let mut variables = Map::new();
+        if let Some(pagination) = pagination {
+            variables.insert("page".to_string(), json!(pagination.page.saturating_sub(1)));
+            variables.insert("perPage".to_string(), json!(pagination.per_page));
+        }
+        if let Some(sort) = sort {
+            variables.insert("sortField".to_string(), json!(sort.field));
+            let order = sort.order.to_string().to_uppercase();
+            variables.insert("sortOrder".to_string(), json!(order));
+        }
+        variables.insert("filter".to_string(), Value::Object(filter));
+
+        let data = match &ops.config.list {
+            Some(template) => self.execute(template, Value::Object(variables)).await?,
+            None => {
+                let filter = arguments(variables.get("filter").into_iter().map(|f| ("filter", f)));
+                let query = format!(
+                    "query {{ items: all{}{} {} total: _all{}Meta{} {{ count }} }}",
+                    ops.plural,
+                    arguments(variables.iter().map(|(k, v)| (k.as_str(), v))),
+                    ops.selection,
+                    ops.plural,
+                    filter,
+                );
+                self.execute(&query, Value::Null).await?
+            }
+        };
+
+        let records = match data.get("items") {
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| Record::from_json(item.clone(), &["id".to_string()], ""))
+                .collect::<Result<Vec<_>>>()?,
+            _ => {
+                return Err(Error::Response(
+                    "no `items` in the GraphQL response".to_string(),
+                ))
+            }
+        };
+        let total = data.get("total").and_then(|total| {
+            total
+                .as_u64()
+                .or_else(|| total.get("count").and_then(Value::as_u64))
+                .map(|n| n as usize)
+        });
+        Ok((records, total))
+    }
+
+    // Runs a query or mutation returning one record aliased `item`. Generated
+    // operations take `arguments` inline, templates as variables.
+    async fn fetch_one(
+        &self,
+        ops: &Operations<'_>,
+        template: Option<&String>,
+        // `query` or `mutation`, and the field of the generated operation.
+        (kind, operation): (&str, &str),
+        args: Vec<(&str, Value)>,
+        variables: Value,
+    ) -> Result<Record> {
+        let data = match template {
+            Some(template) => self.execute(template, variables).await?,
+            None => {
+                let query = format!(
+                    "{} {{ item: {}{} {} }}",
+                    kind,
+                    operation,
+                    arguments(args.iter().map(|(k, v)| (*k, v))),
+                    ops.selection,
+                );
+                self.execute(&query, Value::Null).await?
+            }
+        };
+
+        match data.get("item") {
+            Some(item) if !item.is_null() => {
+                Record::from_json(item.clone(), &["id".to_string()], "")
+            }
+            _ => Err(Error::Response(
+                "no `item` in the GraphQL response".to_string(),
+            )),
+        }
+    }
+
+    async fn update_one(
+        &self,
+        resource: &str,
+        id: Identifier,
+        data: &HashMap<String, Value>,
+    ) -> Result<Record> {
+        let ops = self.operations(resource);
+        let mut args = vec![("id", id_value(&id))];
+        args.extend(data.iter().map(|(k, v)| (k.as_str(), v.clone())));
+        let variables = json!({ "id": id_value(&id), "data": data });
+        let operation = format!("update{}", ops.type_name);
+        let template = ops.config.update.as_ref();
+        self.fetch_one(&ops, template, ("mutation", &operation), args, variables)
+            .await
+    }
+
+    async fn delete_one(&self, resource: &str, id: Identifier) -> Result<Record> {
+        let ops = self.operations(resource);
+        let args = vec![("id", id_value(&id))];
+        let variables = json!({ "id": id_value(&id) });
+        let operation = format!("delete{}", ops.type_name);
+        let template = ops.config.delete.as_ref();
+        self.fetch_one(&ops, template, ("mutation", &operation), args, variables)
+            .await
+    }
+}
+
+#[async_trait]
+impl DataProvider for GraphqlProvider {
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult> {
+        let ops = self.operations(&resource.resource);
+        let filter = params
+            .filter
+            .as_ref()
+            .map(|f| self.filter(ops.config, f))
+            .transpose()?
+            .unwrap_or_default();
+        let (records, total) = self
+            .fetch_list(
+                &ops,
+                params.pagination.as_ref(),
+                params.sort.as_ref(),
+                filter,
+            )
+            .await?;
+        let total = total.unwrap_or(records.len());
+
+        Ok(GetListResult {
+            data: records,
+            total: Some(total),
+            page_info: params.pagination.map(|p| PageInfo::new(&p, total)),
+            meta: None,
+        })
+    }
+
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult> {
+        let ops = self.operations(&resource.resource);
+        let id = id_value(&params.id);
+        let args = vec![("id", id.clone())];
+        let variables = json!({ "id": id });
+        let template = ops.config.one.as_ref();
+        let record = self
+            .fetch_one(&ops, template, ("query", &ops.type_name), args, variables)
+            .await?;
+        Ok(GetOneResult { data: record })
+    }
+
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult> {
+        let ops = self.operations(&resource.resource);
+        let ids = params.ids.iter().map(id_value).collect();
+        let filter = Map::from_iter([("ids".to_string(), Value::Array(ids))]);
+        let (records, _) = self.fetch_list(&ops, None, None, filter).await?;
+        Ok(GetManyResult { data: records })
+    }
+
+    // The target names the related resource and its foreign key field as
+    // `resource.field`. Without a field the singular of the resource
+    // followed by `Id` is assumed, as in `postId`.
+    async fn get_many_reference(
+        &self,
+        resource: Resource,
+        params: GetManyReferenceParams,
+    ) -> Result<GetManyReferenceResult> {
+        let (target, key) = match params.target.split_once('.') {
+            Some((target, key)) => (target.to_string(), key.to_string()),
+            None => (
+                params.target.clone(),
+                format!("{}Id", singular(&resource.resource)),
+            ),
+        };
+
+        let ops = self.operations(&target);
+        let mut filter = self.filter(ops.config, &params.filter)?;
+        filter.insert(key, id_value(&params.id));
+        let (records, total) = self
+            .fetch_list(&ops, Some(&params.pagination), Some(&params.sort), filter)
+            .await?;
+        let total = total.unwrap_or(records.len());
+
+        Ok(GetManyReferenceResult {
+            data: records,
+            total: Some(total),
+            page_info: Some(PageInfo::new(&params.pagination, total)),
+            meta: None,
+        })
+    }
+
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult> {
+        let ops = self.operations(&resource.resource);
+        let args = params
+            .data
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+        let variables = json!({ "data": params.data });
+        let operation = format!("create{}", ops.type_name);
+        let template = ops.config.create.as_ref();
+        let record = self
+            .fetch_one(&ops, template, ("mutation", &operation), args, variables)
+            .await?;
+        Ok(CreateResult { data: record })
+    }
+
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult> {
+        let record = self
+            .update_one(&resource.resource, params.id, &params.data)
+            .await?;
+        Ok(UpdateResult { data: record })
+    }
+
+    async fn update_many(
+        &self,
+        resource: Resource,
+        params: UpdateManyParams,
+    ) -> Result<UpdateManyResult> {
+        let (resource, data) = (&resource.resource, &params.data);
+        let outcomes = stream::iter(params.ids)
+            .map(|id| async move {
+                let result = self.update_one(resource, id.clone(), data).await;
+                ItemOutcome {
+                    id,
+                    result: result.map(|_| ()),
+                }
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(UpdateManyResult { data: outcomes })
+    }
+
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult> {
+        let record = self.delete_one(&resource.resource, params.id).await?;
+        Ok(DeleteResult {
+            data: params.previous_data.unwrap_or(record),
+        })
+    }
+
+    async fn delete_many(
+        &self,
+        resource: Resource,
+        params: DeleteManyParams,
+    ) -> Result<DeleteManyResult> {
+        let resource = &resource.resource;
+        let outcomes = stream::iter(params.ids)
+            .map(|id| async move {
+                let result = self.delete_one(resource, id.clone()).await;
+                ItemOutcome {
+                    id,
+                    result: result.map(|_| ()),
+                }
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(DeleteManyResult { data: outcomes })
+    }
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn id_value(id: &Identifier) -> Value {
+    match id {
+        Identifier::Num(n) => json!(n),
+        Identifier::Str(s) => json!(s),
+    }
+}
+
+// Renders arguments as `(name: literal, ...)`, or nothing without any.
+fn arguments<'a>(args: impl Iterator<Item = (&'a str, &'a Value)>) -> String {
+    let args: Vec<String> = args
+        .map(|(name, value)| format!("{}: {}", name, literal(value)))
+        .collect();
+    if args.is_empty() {
+        return String::new();
+    }
+    format!("({})", args.join(", "))
+}
+
+// Renders a JSON value as a GraphQL input literal, whose objects have bare
+// keys.
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(literal).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => {
+            let fields: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, literal(v)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,90 @@
+use crate::prelude::*;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+use crate::config::HttpConfig;
+use crate::error::HttpError;
+
+const DEFAULT_USER_AGENT: &str = concat!("rtui2/", env!("CARGO_PKG_VERSION"));
+
+// Builds the client a provider keeps for its whole life, so connections and
+// TLS sessions are pooled across requests.
+pub fn build_client(config: &HttpConfig) -> Result<reqwest::Client> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| Error::Unknown(format!("Invalid header name {}", name)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| Error::Unknown(format!("Invalid value for header {}", name)))?;
+        headers.insert(name, value);
+    }
+
+    let mut builder = reqwest::Client::builder().default_headers(headers);
+    if let Some(timeout) = config.timeout {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    if let Some(timeout) = config.connect_timeout {
+        builder = builder.connect_timeout(Duration::from_secs(timeout));
+    }
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    let user_agent = config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+    builder = builder.user_agent(user_agent);
+
+    Ok(builder.build()?)
+}
+
+// Sends a request through the shared client, turning non-2xx responses into
+// `Error::Http`.
+pub async fn send(
+    client: &reqwest::Client,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response> {
+    let request = request.build()?;
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let response = client.execute(request).await?;
+    check_status(method, &url, response).await
+}
+
+// Sends one request of a bulk operation, failures are reported per id
+// instead of aborting the others.
+pub async fn send_one(client: &reqwest::Client, request: reqwest::RequestBuilder) -> Result<()> {
+    send(client, request).await.map(|_| ())
+}
+
+pub async fn check_status(
+    method: reqwest::Method,
+    url: &str,
+    response: reqwest::Response,
+) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(HttpError::new(status, method, url, &body).into())
+}
+
+// Renders a filter operand as a query string value, without quoting strings.
+pub fn query_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// `_like` takes a regular expression, so user input is matched literally.
+pub fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,191 @@
+use crate::prelude::*;
+
+use async_trait::async_trait;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::config::Config;
+use crate::provider::*;
+use crate::providers::store::Store;
+
+// JsonFileProvider serves a json-server `db.json` file: top-level keys are
+// resources and their values arrays of records. Queries run in memory and
+// every write rewrites the file.
+pub struct JsonFileProvider {
+    path: Arc<PathBuf>,
+    store: Arc<Mutex<Store>>,
+    // Taken for the whole of a write, so writes apply one after the other
+    // while reads only wait for the store to be swapped.
+    writing: Arc<Mutex<()>>,
+}
+
+impl JsonFileProvider {
+    pub fn new(config: &Config) -> Result<Self> {
+        let path = PathBuf::from(config.url.trim_start_matches("file://"));
+        let db = serde_json::from_str(&fs::read_to_string(&path)?)?;
+
+        Ok(Self {
+            path: Arc::new(path),
+            store: Arc::new(Mutex::new(Store::from_value(db)?)),
+            writing: Arc::new(Mutex::new(())),
+        })
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&Store) -> Result<T>) -> Result<T> {
+        let store = lock(&self.store)?;
+        f(&store)
+    }
+
+    // Applies `f` to a copy of the store and keeps it once saved, so the
+    // file and the memory never disagree. Saving blocks on the disk, so it
+    // runs on the blocking pool rather than on a runtime worker.
+    async fn write<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut Store) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let (path, store, writing) = (self.path.clone(), self.store.clone(), self.writing.clone());
+        tokio::task::spawn_blocking(move || {
+            let _writing = lock(&writing)?;
+            let mut copy = lock(&store)?.clone();
+            let result = f(&mut copy)?;
+            save(&path, &copy)?;
+            *lock(&store)? = copy;
+            Ok(result)
+        })
+        .await
+        .map_err(|e| Error::Unknown(e.to_string()))?
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>> {
+    mutex
+        .lock()
+        .map_err(|_| Error::Unknown("db.json store poisoned".to_string()))
+}
+
+// Writes a temporary file next to the database and renames it over the
+// database, which is atomic on the same file system.
+fn save(path: &Path, store: &Store) -> Result<()> {
+    let mut temporary = path.to_path_buf().into_os_string();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let mut file = fs::File::create(&temporary)?;
+    file.write_all(serde_json::to_string_pretty(&store.to_value())?.as_bytes())?;
+    file.write_all(b"\n")?;
+    file.sync_all()?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[async_trait]
+impl DataProvider for JsonFileProvider {
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult> {
+        let (records, total) = self.read(|store| {
+            store.list(
+                &resource.resource,
+                params.filter.as_ref(),
+                params.sort.as_ref(),
+                params.pagination.as_ref(),
+            )
+        })?;
+
+        Ok(GetListResult {
+            data: records,
+            total: Some(total),
+            page_info: params.pagination.map(|p| PageInfo::new(&p, total)),
+            meta: None,
+        })
+    }
+
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult> {
+        let record = self.read(|store| store.one(&resource.resource, &params.id))?;
+        Ok(GetOneResult { data: record })
+    }
+
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult> {
+        let records = self.read(|store| store.many(&resource.resource, &params.ids))?;
+        Ok(GetManyResult { data: records })
+    }
+
+    async fn get_many_reference(
+        &self,
+        resource: Resource,
+        params: GetManyReferenceParams,
+    ) -> Result<GetManyReferenceResult> {
+        let (records, total) =
+            self.read(|store| store.many_reference(&resource.resource, &params))?;
+
+        Ok(GetManyReferenceResult {
+            data: records,
+            total: Some(total),
+            page_info: Some(PageInfo::new(&params.pagination, total)),
+            meta: None,
+        })
+    }
+
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult> {
+        let record = self
+            .write(move |store| store.create(&resource.resource, params.data))
+            .await?;
+        Ok(CreateResult { data: record })
+    }
+
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult> {
+        let record = self
+            .write(move |store| store.update(&resource.resource, &params.id, &params.data))
+            .await?;
+        Ok(UpdateResult { data: record })
+    }
+
+    async fn update_many(
+        &self,
+        resource: Resource,
+        params: UpdateManyParams,
+    ) -> Result<UpdateManyResult> {
+        let outcomes = self
+            .write(move |store| {
+                Ok(params
+                    .ids
+                    .into_iter()
+                    .map(|id| {
+                        let result = store
+                            .update(&resource.resource, &id, &params.data)
+                            .map(|_| ());
+                        ItemOutcome { id, result }
+                    })
+                    .collect())
+            })
+            .await?;
+        Ok(UpdateManyResult { data: outcomes })
+    }
+
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult> {
+        let record = self
+            .write(move |store| store.delete(&resource.resource, &params.id))
+            .await?;
+        Ok(DeleteResult { data: record })
+    }
+
+    async fn delete_many(
+        &self,
+        resource: Resource,
+        params: DeleteManyParams,
+    ) -> Result<DeleteManyResult> {
+        let outcomes = self
+            .write(move |store| {
+                Ok(params
+                    .ids
+                    .into_iter()
+                    .map(|id| {
+                        let result = store.delete(&resource.resource, &id).map(|_| ());
+                        ItemOutcome { id, result }
+                    })
+                    .collect())
+            })
+            .await?;
+        Ok(DeleteManyResult { data: outcomes })
+    }
+}
@@ -1,86 +1,159 @@
 use crate::prelude::*;
 
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
 use std::collections::HashMap;
 
+use crate::config::HttpConfig;
 use crate::provider::*;
+use crate::providers::http::{self, build_client, escape_regex, query_value};
 use url::{ParseError, Url};
 
 pub struct JsonPlaceholder {
     url: Url,
+    client: reqwest::Client,
+    // Requests in flight at once during bulk operations.
+    concurrency: usize,
 }
 
 impl JsonPlaceholder {
-    pub fn new(url: &str) -> Result<Self> {
+    pub fn new(url: &str, http: &HttpConfig) -> Result<Self> {
         Ok(Self {
             url: Url::parse(url)?,
+            client: build_client(http)?,
+            concurrency: http.concurrency(),
         })
     }
 
-    fn check_status(&self, response: &reqwest::Response) -> Result<()> {
-        if !response.status().is_success() {
-            return Err(Error::RequestStatus(format!(
-                "Received non-200 response: {}",
-                response.status()
-            )));
-        }
-
-        Ok(())
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        http::send(&self.client, request).await
     }
 
+    // Translates filters into json-server operators. There is no strict
+    // comparison, so `gt`/`lt` are expressed as `gte`/`lte` plus `ne`.
     fn build_filter_query_params(&self, filters: &FilterPayload) -> String {
-        filters
+        let mut params: Vec<(String, String)> = Vec::new();
+        for condition in &filters.conditions {
+            let field = &condition.field;
+            match &condition.filter {
+                Filter::Eq(v) => params.push((field.clone(), query_value(v))),
+                Filter::Ne(v) => params.push((format!("{}_ne", field), query_value(v))),
+                Filter::Gt(v) => {
+                    params.push((format!("{}_gte", field), query_value(v)));
+                    params.push((format!("{}_ne", field), query_value(v)));
+                }
+                Filter::Gte(v) => params.push((format!("{}_gte", field), query_value(v))),
+                Filter::Lt(v) => {
+                    params.push((format!("{}_lte", field), query_value(v)));
+                    params.push((format!("{}_ne", field), query_value(v)));
+                }
+                Filter::Lte(v) => params.push((format!("{}_lte", field), query_value(v))),
+                Filter::Range(from, to) => {
+                    params.push((format!("{}_gte", field), query_value(from)));
+                    params.push((format!("{}_lte", field), query_value(to)));
+                }
+                Filter::Contains(v) => params.push((format!("{}_like", field), escape_regex(v))),
+                Filter::In(values) => {
+                    for v in values {
+                        params.push((field.clone(), query_value(v)));
+                    }
+                }
+            }
+        }
+        if let Some(q) = &filters.q {
+            params.push(("q".to_string(), q.clone()));
+        }
+
+        params
             .iter()
-            .map(|(k, v)| format!("{}={}", k, urlencoding::encode(&v.to_string())))
+            .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
             .collect::<Vec<_>>()
             .join("&")
     }
 
     fn update_url_with_filters(&self, url: &str, filters: &FilterPayload) -> String {
+        if filters.is_empty() {
+            return url.to_string();
+        }
         let filters = self.build_filter_query_params(filters);
-        format!("{}?{}", url, filters)
+        self.update_url_with_query(url, &filters)
+    }
+
+    fn update_url_with_pagination(&self, url: &str, pagination: &PaginationPayload) -> String {
+        let pagination = format!("_page={}&_limit={}", pagination.page, pagination.per_page);
+        self.update_url_with_query(url, &pagination)
+    }
+
+    fn update_url_with_sort(&self, url: &str, sort: &SortPayload) -> String {
+        let sort = format!(
+            "_sort={}&_order={}",
+            urlencoding::encode(&sort.field),
+            sort.order
+        );
+        self.update_url_with_query(url, &sort)
+    }
+
+    fn update_url_with_query(&self, url: &str, query: &str) -> String {
+        let separator = if url.contains('?') { "&" } else { "?" };
+        format!("{}{}{}", url, separator, query)
+    }
+
+    // json-server reports the size of the whole collection in `X-Total-Count`.
+    fn total_count(&self, response: &reqwest::Response) -> Option<usize> {
+        response
+            .headers()
+            .get("X-Total-Count")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
     }
 
-    async fn my_get_list(&self, url: &str) -> Result<GetListResult> {
-        let response = reqwest::get(url).await?;
-        self.check_status(&response)?;
+    async fn my_get_list(
+        &self,
+        url: &str,
+        pagination: Option<&PaginationPayload>,
+    ) -> Result<GetListResult> {
+        let response = self.send(self.client.get(url)).await?;
+        let total = self.total_count(&response);
         let body = response.text().await?;
-        let records: Vec<Record> = serde_json::from_str(&body).unwrap();
-        let total: usize = records.len() as usize;
+        let records: Vec<Record> = serde_json::from_str(&body)?;
+        let total: usize = total.unwrap_or(records.len());
         Ok(GetListResult {
             data: records,
             total: Some(total),
             meta: None,
-            page_info: None,
+            page_info: pagination.map(|p| PageInfo::new(p, total)),
         })
     }
 
     async fn my_get_one(&self, url: &str) -> Result<GetOneResult> {
-        let response = reqwest::get(url).await?;
-        self.check_status(&response)?;
+        let response = self.send(self.client.get(url)).await?;
         let body = response.text().await?;
         let records: Record = serde_json::from_str(&body)?;
         Ok(GetOneResult { data: records })
     }
 
     async fn my_get_many(&self, url: &str) -> Result<GetManyResult> {
-        let response = reqwest::get(url).await?;
-        self.check_status(&response)?;
+        let response = self.send(self.client.get(url)).await?;
         let body = response.text().await?;
         let records: Vec<Record> = serde_json::from_str(&body)?;
         Ok(GetManyResult { data: records })
     }
 
-    async fn my_get_many_reference(&self, url: &str) -> Result<GetManyReferenceResult> {
-        let response = reqwest::get(url).await?;
-        self.check_status(&response)?;
+    async fn my_get_many_reference(
+        &self,
+        url: &str,
+        pagination: &PaginationPayload,
+    ) -> Result<GetManyReferenceResult> {
+        let response = self.send(self.client.get(url)).await?;
+        let total = self.total_count(&response);
         let body = response.text().await?;
         let records: Vec<Record> = serde_json::from_str(&body)?;
-        let total: usize = records.len() as usize;
+        let total: usize = total.unwrap_or(records.len());
 
         Ok(GetManyReferenceResult {
             data: records,
             total: if total > 0 { Some(total) } else { None },
-            page_info: None,
+            page_info: Some(PageInfo::new(pagination, total)),
             meta: None,
         })
     }
@@ -90,10 +163,7 @@ impl JsonPlaceholder {
         url: &str,
         data: HashMap<String, serde_json::Value>,
     ) -> Result<CreateResult> {
-        let client = reqwest::Client::new();
-
-        let response = client.post(url).json(&data).send().await?;
-        self.check_status(&response)?;
+        let response = self.send(self.client.post(url).json(&data)).await?;
         let body = response.text().await?;
         let records: Record = serde_json::from_str(&body)?;
         Ok(CreateResult { data: records })
@@ -104,40 +174,37 @@ impl JsonPlaceholder {
         url: &str,
         data: HashMap<String, serde_json::Value>,
     ) -> Result<UpdateResult> {
-        let client = reqwest::Client::new();
-
-        let response = client.put(url).json(&data).send().await?;
-        self.check_status(&response)?;
+        let response = self.send(self.client.put(url).json(&data)).await?;
         let body = response.text().await?;
         let records: Record = serde_json::from_str(&body)?;
         Ok(UpdateResult { data: records })
     }
 
+    // Bulk edits only carry the fields filled in, so they are patched in
+    // instead of replacing the records.
     async fn my_update_many(
         &self,
         url: &str,
         ids: Vec<Identifier>,
         data: HashMap<String, serde_json::Value>,
     ) -> Result<UpdateManyResult> {
-        let client = reqwest::Client::new();
-
-        let mut updates_ids = Vec::new();
-        for id in ids {
-            let url_with_id = format!("{}{}{}", url, "/", id);
-            let response = client.put(url).json(&data).send().await?;
-            if self.check_status(&response).is_ok() {
-                updates_ids.push(id);
-            }
-        }
+        let data = &data;
+        let outcomes = stream::iter(ids)
+            .map(|id| async move {
+                let url_with_id = format!("{}/{}", url.trim_end_matches('/'), id);
+                let request = self.client.patch(url_with_id).json(data);
+                let result = http::send_one(&self.client, request).await;
+                ItemOutcome { id, result }
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
 
-        Ok(UpdateManyResult { data: updates_ids })
+        Ok(UpdateManyResult { data: outcomes })
     }
 
     async fn my_delete(&self, url: &str, data: Option<Record>) -> Result<DeleteResult> {
-        let client = reqwest::Client::new();
-
-        let response = client.delete(url).send().await?;
-        self.check_status(&response)?;
+        self.send(self.client.delete(url)).await?;
         match data {
             Some(record) => Ok(DeleteResult { data: record }),
             None => Err(Error::Unknown("DetelteParams wrong".to_string())),
@@ -145,143 +212,114 @@ impl JsonPlaceholder {
     }
 
     async fn my_delete_many(&self, url: &str, ids: Vec<Identifier>) -> Result<DeleteManyResult> {
-        let client = reqwest::Client::new();
-
-        let mut deleted_ids = Vec::new();
-        for id in ids {
-            let url_with_id = format!("{}{}{}", url, "/", id);
-            let response = client.delete(url_with_id).send().await?;
-            if self.check_status(&response).is_ok() {
-                deleted_ids.push(id);
-            }
-        }
+        let outcomes = stream::iter(ids)
+            .map(|id| async move {
+                let url_with_id = format!("{}/{}", url.trim_end_matches('/'), id);
+                let result = http::send_one(&self.client, self.client.delete(url_with_id)).await;
+                ItemOutcome { id, result }
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
 
-        Ok(DeleteManyResult { data: deleted_ids })
+        Ok(DeleteManyResult { data: outcomes })
     }
 }
 
+#[async_trait]
 impl DataProvider for JsonPlaceholder {
-    fn get_list(
-        &self,
-        resource: Resource,
-        params: GetListParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<GetListResult>> + '_>> {
-        Box::pin(async move {
-            let res = resource.resource;
-            let mut url = self.url.join(&res)?.to_string();
-            if params.filter.is_some() {
-                url = self.update_url_with_filters(&url, &params.filter.unwrap());
-            }
-            self.my_get_list(&url).await
-        })
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult> {
+        let res = resource.resource;
+        let mut url = self.url.join(&res)?.to_string();
+        if let Some(filter) = &params.filter {
+            url = self.update_url_with_filters(&url, filter);
+        }
+        if let Some(sort) = &params.sort {
+            url = self.update_url_with_sort(&url, sort);
+        }
+        if let Some(pagination) = &params.pagination {
+            url = self.update_url_with_pagination(&url, pagination);
+        }
+        self.my_get_list(&url, params.pagination.as_ref()).await
     }
-    fn get_one(
-        &self,
-        resource: Resource,
-        params: GetOneParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<GetOneResult>> + '_>> {
-        Box::pin(async move {
-            let resource_path = format!("{}{}", resource.resource, "/");
-            let url = self.url.join(&resource_path)?;
-            let id: String = params.id.into();
-            let url_with_id = url.join(&id)?.to_string();
-            self.my_get_one(&url_with_id).await
-        })
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult> {
+        let resource_path = format!("{}{}", resource.resource, "/");
+        let url = self.url.join(&resource_path)?;
+        let id: String = params.id.into();
+        let url_with_id = url.join(&id)?.to_string();
+        self.my_get_one(&url_with_id).await
     }
-    fn get_many(
-        &self,
-        resource: Resource,
-        params: GetManyParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<GetManyResult>> + '_>> {
-        Box::pin(async move {
-            let ids_query_param = params
-                .ids
-                .iter()
-                .map(|id| format!("id={}", id))
-                .collect::<Vec<_>>()
-                .join("&");
-            let resource_path = format!("{}{}{}", resource.resource, "?", ids_query_param);
-            let url = self.url.join(&resource_path)?.to_string();
-            self.my_get_many(&url).await
-        })
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult> {
+        let ids_query_param = params
+            .ids
+            .iter()
+            .map(|id| format!("id={}", id))
+            .collect::<Vec<_>>()
+            .join("&");
+        let resource_path = format!("{}{}{}", resource.resource, "?", ids_query_param);
+        let url = self.url.join(&resource_path)?.to_string();
+        self.my_get_many(&url).await
     }
 
-    fn get_many_reference(
+    async fn get_many_reference(
         &self,
         resource: Resource,
         params: GetManyReferenceParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<GetManyReferenceResult>> + '_>>
-    {
-        Box::pin(async move {
-            let resource_path = format!("{}{}", resource.resource, "/");
-            let id_path = format!("{}{}", &params.id.to_string(), "/");
-            let url = self.url.join(&resource_path)?;
-            let url_with_id = url.join(&id_path)?;
-            let mut url_with_id_and_target = url_with_id.join(&params.target)?.to_string();
-            url_with_id_and_target =
-                self.update_url_with_filters(&url_with_id_and_target, &params.filter);
-            self.my_get_many_reference(&url_with_id_and_target).await
-        })
+    ) -> Result<GetManyReferenceResult> {
+        let resource_path = format!("{}{}", resource.resource, "/");
+        let id_path = format!("{}{}", &params.id.to_string(), "/");
+        let url = self.url.join(&resource_path)?;
+        let url_with_id = url.join(&id_path)?;
+        let mut url_with_id_and_target = url_with_id.join(&params.target)?.to_string();
+        url_with_id_and_target =
+            self.update_url_with_filters(&url_with_id_and_target, &params.filter);
+        url_with_id_and_target = self.update_url_with_sort(&url_with_id_and_target, &params.sort);
+        url_with_id_and_target =
+            self.update_url_with_pagination(&url_with_id_and_target, &params.pagination);
+        self.my_get_many_reference(&url_with_id_and_target, &params.pagination)
+            .await
     }
 
-    fn create(
-        &self,
-        resource: Resource,
-        params: CreateParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<CreateResult>> + '_>> {
-        Box::pin(async move {
-            let url = self.url.join(&resource.resource)?.to_string();
-            self.my_create(&url, params.data).await
-        })
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult> {
+        let url = self.url.join(&resource.resource)?.to_string();
+        self.my_create(&url, params.data).await
     }
 
-    fn update(
-        &self,
-        resource: Resource,
-        params: UpdateParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<UpdateResult>> + '_>> {
-        Box::pin(async move {
-            let resource_path = format!("{}{}", resource.resource, "/");
-            let url = self.url.join(&resource_path)?;
-            let url_with_id = url.join(&params.id.to_string())?.to_string();
-            self.my_update(&url_with_id, params.data).await
-        })
+    // `PUT` replaces the whole record, so the fields left out of `data`
+    // are sent as they were.
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult> {
+        let resource_path = format!("{}{}", resource.resource, "/");
+        let url = self.url.join(&resource_path)?;
+        let url_with_id = url.join(&params.id.to_string())?.to_string();
+        let mut data = params.previous_data.fields;
+        data.extend(params.data);
+        self.my_update(&url_with_id, data).await
     }
 
-    fn update_many(
+    async fn update_many(
         &self,
         resource: Resource,
         params: UpdateManyParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<UpdateManyResult>> + '_>> {
-        Box::pin(async move {
-            let resource_path = format!("{}{}", resource.resource, "/");
-            let url = self.url.join(&resource_path)?.to_string();
-            self.my_update_many(&url, params.ids, params.data).await
-        })
+    ) -> Result<UpdateManyResult> {
+        let resource_path = format!("{}{}", resource.resource, "/");
+        let url = self.url.join(&resource_path)?.to_string();
+        self.my_update_many(&url, params.ids, params.data).await
     }
 
-    fn delete(
-        &self,
-        resource: Resource,
-        params: DeleteParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<DeleteResult>> + '_>> {
-        Box::pin(async move {
-            let resource_path = format!("{}{}", resource.resource, "/");
-            let url = self.url.join(&resource_path)?;
-            let url_with_id = url.join(&params.id.to_string())?.to_string();
-            self.my_delete(&url_with_id, params.previous_data).await
-        })
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult> {
+        let resource_path = format!("{}{}", resource.resource, "/");
+        let url = self.url.join(&resource_path)?;
+        let url_with_id = url.join(&params.id.to_string())?.to_string();
+        self.my_delete(&url_with_id, params.previous_data).await
     }
 
-    fn delete_many(
+    async fn delete_many(
         &self,
         resource: Resource,
         params: DeleteManyParams,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<DeleteManyResult>> + '_>> {
-        Box::pin(async move {
-            let resource_path = format!("{}{}", resource.resource, "/");
-            let url = self.url.join(&resource_path)?.to_string();
-            self.my_delete_many(&url, params.ids).await
-        })
+    ) -> Result<DeleteManyResult> {
+        let resource_path = format!("{}{}", resource.resource, "/");
+        let url = self.url.join(&resource_path)?.to_string();
+        self.my_delete_many(&url, params.ids).await
     }
 }
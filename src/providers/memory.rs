@@ -0,0 +1,330 @@
+use crate::prelude::*;
+
+use async_trait::async_trait;
+use chrono::{Days, NaiveDate};
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::{self, Config, FieldType};
+use crate::error::HttpError;
+use crate::provider::*;
+use crate::providers::store::{foreign_key, Store};
+
+// Failures injected into the calls of an `InMemoryProvider`.
+#[derive(Debug, Default, Clone)]
+pub struct Failures {
+    // Every nth call fails.
+    pub every: Option<usize>,
+    // Calls touching these records fail, one by one in bulk operations.
+    pub ids: Vec<Identifier>,
+}
+
+// InMemoryProvider serves collections of records held in memory, with the
+// filtering, sorting and pagination of json-server. Calls can be slowed
+// down and made to fail to try out loading and error states.
+pub struct InMemoryProvider {
+    store: Mutex<Store>,
+    latency: Duration,
+    failures: Failures,
+    calls: AtomicUsize,
+}
+
+impl InMemoryProvider {
+    pub fn new(collections: HashMap<String, Vec<Record>>) -> Self {
+        Self {
+            store: Mutex::new(Store::from_records(collections)),
+            latency: Duration::ZERO,
+            failures: Failures::default(),
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    // Seeds the provider from the `memory` section of the config: a
+    // `db.json` file, or records generated after the configured fields.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let memory = config.memory.clone().unwrap_or_default();
+        let mut provider = match &memory.seed {
+            Some(path) => {
+                let db = serde_json::from_str(&fs::read_to_string(path)?)?;
+                Self {
+                    store: Mutex::new(Store::from_value(db)?),
+                    ..Self::new(HashMap::new())
+                }
+            }
+            None => Self::new(demo_records(config, memory.records())),
+        };
+
+        provider.latency = Duration::from_millis(memory.latency.unwrap_or_default());
+        provider.failures = Failures {
+            every: memory.fail_every,
+            ids: memory.fail_ids,
+        };
+        Ok(provider)
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_failures(mut self, failures: Failures) -> Self {
+        self.failures = failures;
+        self
+    }
+
+    // Waits for the latency, then fails the call if it is its turn to.
+    async fn call(&self, method: Method, resource: &Resource) -> Result<()> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+
+        let count = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        match self.failures.every {
+            Some(every) if every > 0 && count.is_multiple_of(every) => {
+                Err(injected(method, resource, None))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check(&self, method: Method, resource: &Resource, id: &Identifier) -> Result<()> {
+        let id = id.to_string();
+        if self
+            .failures
+            .ids
+            .iter()
+            .any(|failing| failing.to_string() == id)
+        {
+            return Err(injected(method, resource, Some(&id)));
+        }
+        Ok(())
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&Store) -> Result<T>) -> Result<T> {
+        let store = self
+            .store
+            .lock()
+            .map_err(|_| Error::Unknown("memory store poisoned".to_string()))?;
+        f(&store)
+    }
+
+    fn write<T>(&self, f: impl FnOnce(&mut Store) -> Result<T>) -> Result<T> {
+        let mut store = self
+            .store
+            .lock()
+            .map_err(|_| Error::Unknown("memory store poisoned".to_string()))?;
+        f(&mut store)
+    }
+}
+
+// Injected failures look like a server out of service, so they go through
+// the same paths as real ones.
+fn injected(method: Method, resource: &Resource, id: Option<&str>) -> Error {
+    let url = match id {
+        Some(id) => format!("memory://{}/{}", resource.resource, id),
+        None => format!("memory://{}", resource.resource),
+    };
+    Error::Http(Box::new(HttpError::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        method,
+        &url,
+        "injected failure",
+    )))
+}
+
+// Generates `count` records for every resource and relation of the config,
+// references pointing at the records generated for their resource. The
+// records of a resource win over those of a relation targeting it.
+fn demo_records(config: &Config, count: usize) -> HashMap<String, Vec<Record>> {
+    let mut collections = HashMap::new();
+    for resource in &config.resources {
+        let name = Resource::new(&resource.resource).resource;
+        let records = (1..=count)
+            .map(|n| demo_record(&resource.fields, n, count))
+            .collect();
+
+        for relation in &resource.relations {
+            let (target, key) = match relation.target.split_once('.') {
+                Some((target, key)) => (target.to_string(), key.to_string()),
+                None => (relation.target.clone(), foreign_key(&name)),
+            };
+            let target = Resource::new(&target).resource;
+            // Two related records for each of the first half of the records.
+            collections.entry(target).or_insert_with(|| {
+                (1..=count)
+                    .map(|n| {
+                        let mut record = demo_record(&relation.fields, n, count);
+                        record
+                            .fields
+                            .insert(key.clone(), Value::from(n.div_ceil(2)));
+                        record
+                    })
+                    .collect()
+            });
+        }
+
+        collections.insert(name, records);
+    }
+    collections
+}
+
+fn demo_record(fields: &[config::Field], n: usize, count: usize) -> Record {
+    let fields = fields
+        .iter()
+        .filter(|field| field.name != "id")
+        .map(|field| (field.name.clone(), demo_value(field, n, count)))
+        .collect();
+    Record {
+        id: Identifier::Num(n),
+        fields,
+    }
+}
+
+fn demo_value(field: &config::Field, n: usize, count: usize) -> Value {
+    match field.kind {
+        FieldType::String => Value::String(format!("{} {}", field.name, n)),
+        FieldType::Number => {
+            let min = field.min.unwrap_or(0.0);
+            let value = match field.max {
+                Some(max) if max > min => min + (n as f64 % (max - min + 1.0)),
+                _ => min + n as f64,
+            };
+            Value::from(value as i64)
+        }
+        FieldType::Bool => Value::Bool(n.is_multiple_of(2)),
+        FieldType::Date => {
+            let date = NaiveDate::from_ymd_opt(2024, 1, 1)
+                .and_then(|date| date.checked_add_days(Days::new(n as u64)))
+                .unwrap_or_default();
+            Value::String(date.format(field.date_format()).to_string())
+        }
+        FieldType::Enum => field
+            .choices
+            .get(n % field.choices.len().max(1))
+            .map_or(Value::Null, |choice| Value::String(choice.clone())),
+        FieldType::Reference => Value::from(n % count.max(1) + 1),
+        FieldType::Json => serde_json::json!({ "n": n }),
+    }
+}
+
+#[async_trait]
+impl DataProvider for InMemoryProvider {
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult> {
+        self.call(Method::GET, &resource).await?;
+        let (records, total) = self.read(|store| {
+            store.list(
+                &resource.resource,
+                params.filter.as_ref(),
+                params.sort.as_ref(),
+                params.pagination.as_ref(),
+            )
+        })?;
+
+        Ok(GetListResult {
+            data: records,
+            total: Some(total),
+            page_info: params.pagination.map(|p| PageInfo::new(&p, total)),
+            meta: None,
+        })
+    }
+
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult> {
+        self.call(Method::GET, &resource).await?;
+        self.check(Method::GET, &resource, &params.id)?;
+        let record = self.read(|store| store.one(&resource.resource, &params.id))?;
+        Ok(GetOneResult { data: record })
+    }
+
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult> {
+        self.call(Method::GET, &resource).await?;
+        let records = self.read(|store| store.many(&resource.resource, &params.ids))?;
+        Ok(GetManyResult { data: records })
+    }
+
+    async fn get_many_reference(
+        &self,
+        resource: Resource,
+        params: GetManyReferenceParams,
+    ) -> Result<GetManyReferenceResult> {
+        self.call(Method::GET, &resource).await?;
+        let (records, total) =
+            self.read(|store| store.many_reference(&resource.resource, &params))?;
+
+        Ok(GetManyReferenceResult {
+            data: records,
+            total: Some(total),
+            page_info: Some(PageInfo::new(&params.pagination, total)),
+            meta: None,
+        })
+    }
+
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult> {
+        self.call(Method::POST, &resource).await?;
+        let record = self.write(|store| store.create(&resource.resource, params.data))?;
+        Ok(CreateResult { data: record })
+    }
+
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult> {
+        self.call(Method::PATCH, &resource).await?;
+        self.check(Method::PATCH, &resource, &params.id)?;
+        let record =
+            self.write(|store| store.update(&resource.resource, &params.id, &params.data))?;
+        Ok(UpdateResult { data: record })
+    }
+
+    async fn update_many(
+        &self,
+        resource: Resource,
+        params: UpdateManyParams,
+    ) -> Result<UpdateManyResult> {
+        self.call(Method::PATCH, &resource).await?;
+        let outcomes = self.write(|store| {
+            Ok(params
+                .ids
+                .into_iter()
+                .map(|id| {
+                    let result = self.check(Method::PATCH, &resource, &id).and_then(|_| {
+                        store
+                            .update(&resource.resource, &id, &params.data)
+                            .map(|_| ())
+                    });
+                    ItemOutcome { id, result }
+                })
+                .collect())
+        })?;
+        Ok(UpdateManyResult { data: outcomes })
+    }
+
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult> {
+        self.call(Method::DELETE, &resource).await?;
+        self.check(Method::DELETE, &resource, &params.id)?;
+        let record = self.write(|store| store.delete(&resource.resource, &params.id))?;
+        Ok(DeleteResult { data: record })
+    }
+
+    async fn delete_many(
+        &self,
+        resource: Resource,
+        params: DeleteManyParams,
+    ) -> Result<DeleteManyResult> {
+        self.call(Method::DELETE, &resource).await?;
+        let outcomes = self.write(|store| {
+            Ok(params
+                .ids
+                .into_iter()
+                .map(|id| {
+                    let result = self
+                        .check(Method::DELETE, &resource, &id)
+                        .and_then(|_| store.delete(&resource.resource, &id).map(|_| ()));
+                    ItemOutcome { id, result }
+                })
+                .collect())
+        })?;
+        Ok(DeleteManyResult { data: outcomes })
+    }
+}
@@ -1,5 +1,21 @@
 use crate::prelude::*;
 
+mod graphql;
+mod http;
+mod json_file;
 mod json_placeholder;
+mod memory;
+mod postgrest;
+mod registry;
+mod rest;
+mod sqlite;
+mod store;
 
+pub use graphql::GraphqlProvider;
+pub use json_file::JsonFileProvider;
 pub use json_placeholder::JsonPlaceholder;
+pub use memory::{Failures, InMemoryProvider};
+pub use postgrest::PostgrestProvider;
+pub use registry::{build, Registry};
+pub use rest::RestProvider;
+pub use sqlite::SqliteProvider;
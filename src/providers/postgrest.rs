@@ -0,0 +1,385 @@
+use crate::prelude::*;
+
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_RANGE, RANGE};
+use reqwest::Method;
+use std::collections::HashMap;
+use url::Url;
+
+use crate::config::{self, Config, FieldType};
+use crate::provider::*;
+use crate::providers::http::{self, build_client, query_value};
+
+// Makes PostgREST answer with a single object instead of an array.
+const SINGLE_OBJECT: &str = "application/vnd.pgrst.object+json";
+
+// PostgrestProvider browses the tables and views exposed by PostgREST.
+pub struct PostgrestProvider {
+    url: Url,
+    client: reqwest::Client,
+    schema: Option<String>,
+    id_field: String,
+    // Configured fields of each resource, selected instead of `*`.
+    fields: HashMap<String, Vec<config::Field>>,
+}
+
+impl PostgrestProvider {
+    pub fn new(config: &Config) -> Result<Self> {
+        let mut url = Url::parse(&config.url)?;
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        let postgrest = config.postgrest.clone().unwrap_or_default();
+
+        Ok(Self {
+            url,
+            client: build_client(&config.http)?,
+            schema: postgrest.schema,
+            id_field: postgrest.id_field.unwrap_or_else(|| "id".to_string()),
+            fields: config
+                .resources
+                .iter()
+                .map(|r| (Resource::new(&r.resource).resource, r.fields.clone()))
+                .collect(),
+        })
+    }
+
+    // Builds a request on a table, reads and writes picking the schema with
+    // their own header.
+    fn request(&self, method: Method, table: &str) -> Result<reqwest::RequestBuilder> {
+        let url = self.url.join(table)?;
+        let mut request = self.client.request(method.clone(), url);
+        if let Some(schema) = &self.schema {
+            let header = match method {
+                Method::GET | Method::HEAD => "Accept-Profile",
+                _ => "Content-Profile",
+            };
+            request = request.header(header, schema);
+        }
+        Ok(request)
+    }
+
+    // Writes return the affected rows, as a single object when `single`.
+    fn returning(&self, request: reqwest::RequestBuilder, single: bool) -> reqwest::RequestBuilder {
+        let request = request.header("Prefer", "return=representation");
+        if single {
+            return request.header(ACCEPT, SINGLE_OBJECT);
+        }
+        request
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        http::send(&self.client, request).await
+    }
+
+    fn select(&self, table: &str) -> String {
+        match self.fields.get(table) {
+            Some(fields) if !fields.is_empty() => std::iter::once(self.id_field.as_str())
+                .chain(fields.iter().map(|f| f.name.as_str()))
+                .collect::<Vec<_>>()
+                .join(","),
+            _ => "*".to_string(),
+        }
+    }
+
+    fn id_filter(&self, id: &Identifier) -> (String, String) {
+        (self.id_field.clone(), format!("eq.{}", id))
+    }
+
+    fn ids_filter(&self, ids: &[Identifier]) -> (String, String) {
+        let ids: Vec<String> = ids.iter().map(|id| list_value(&id.to_string())).collect();
+        (self.id_field.clone(), format!("in.({})", ids.join(",")))
+    }
+
+    fn filter_params(&self, table: &str, filters: &FilterPayload) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        for condition in &filters.conditions {
+            let field = condition.field.clone();
+            let value = |op: &str, v: &serde_json::Value| format!("{}.{}", op, query_value(v));
+            match &condition.filter {
+                Filter::Eq(v) => params.push((field, value("eq", v))),
+                Filter::Ne(v) => params.push((field, value("neq", v))),
+                Filter::Gt(v) => params.push((field, value("gt", v))),
+                Filter::Gte(v) => params.push((field, value("gte", v))),
+                Filter::Lt(v) => params.push((field, value("lt", v))),
+                Filter::Lte(v) => params.push((field, value("lte", v))),
+                Filter::Range(from, to) => {
+                    params.push((field.clone(), value("gte", from)));
+                    params.push((field, value("lte", to)));
+                }
+                Filter::Contains(v) => params.push((field, format!("ilike.*{}*", like_value(v)))),
+                Filter::In(values) => {
+                    let values: Vec<String> =
+                        values.iter().map(|v| list_value(&query_value(v))).collect();
+                    params.push((field, format!("in.({})", values.join(","))));
+                }
+            }
+        }
+
+        // The search matches any configured text column.
+        if let Some(q) = &filters.q {
+            let pattern = list_value(&format!("*{}*", like_value(q)));
+            let columns: Vec<String> = self
+                .fields
+                .get(table)
+                .into_iter()
+                .flatten()
+                .filter(|f| f.kind == FieldType::String)
+                .map(|f| format!("{}.ilike.{}", f.name, pattern))
+                .collect();
+            if !columns.is_empty() {
+                params.push(("or".to_string(), format!("({})", columns.join(","))));
+            }
+        }
+        params
+    }
+
+    fn order_param(&self, sort: &SortPayload) -> (String, String) {
+        (
+            "order".to_string(),
+            format!("{}.{}", sort.field, sort.order),
+        )
+    }
+
+    // Fetches a page of rows, asking for the exact count of the whole result.
+    async fn fetch_page(
+        &self,
+        table: &str,
+        mut params: Vec<(String, String)>,
+        pagination: Option<&PaginationPayload>,
+    ) -> Result<(Vec<Record>, Option<usize>)> {
+        params.push(("select".to_string(), self.select(table)));
+        let mut request = self
+            .request(Method::GET, table)?
+            .query(&params)
+            .header("Prefer", "count=exact");
+        if let Some(pagination) = pagination {
+            let from = pagination.page.saturating_sub(1) * pagination.per_page;
+            let to = from + pagination.per_page.max(1) - 1;
+            request = request
+                .header("Range-Unit", "items")
+                .header(RANGE, format!("{}-{}", from, to));
+        }
+
+        let response = self.send(request).await?;
+        let total = content_range_total(response.headers());
+        let rows: Vec<serde_json::Value> = response.json().await?;
+        let records = rows
+            .into_iter()
+            .map(|row| self.record(row))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((records, total))
+    }
+
+    async fn fetch_one(&self, request: reqwest::RequestBuilder) -> Result<Record> {
+        let response = self.send(request).await?;
+        self.record(response.json().await?)
+    }
+
+    fn record(&self, row: serde_json::Value) -> Result<Record> {
+        Record::from_json(row, std::slice::from_ref(&self.id_field), "")
+    }
+
+    // Runs a bulk write in a single request. Ids missing from the returned
+    // rows were not affected, e.g. hidden by a row level security policy.
+    async fn write_many(
+        &self,
+        request: reqwest::RequestBuilder,
+        ids: Vec<Identifier>,
+        verb: &str,
+    ) -> Vec<ItemOutcome> {
+        let request = self.returning(request, false).query(&[
+            self.ids_filter(&ids),
+            ("select".to_string(), self.id_field.clone()),
+        ]);
+        let affected = match self.send(request).await {
+            Ok(response) => response
+                .json::<Vec<serde_json::Value>>()
+                .await
+                .map_err(Error::from),
+            Err(e) => Err(e),
+        };
+
+        // Compared as text, a `text` key may come back as a number or the
+        // other way around.
+        let affected: Result<Vec<String>> = affected.and_then(|rows| {
+            rows.into_iter()
+                .map(|row| self.record(row).map(|r| r.id.to_string()))
+                .collect()
+        });
+        ids.into_iter()
+            .map(|id| {
+                let result = match &affected {
+                    Ok(affected) if affected.contains(&id.to_string()) => Ok(()),
+                    Ok(_) => Err(Error::Response(format!("{} was not {}", id, verb))),
+                    Err(Error::Http(e)) => Err(Error::Http(e.clone())),
+                    Err(e) => Err(Error::Response(e.to_string())),
+                };
+                ItemOutcome { id, result }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl DataProvider for PostgrestProvider {
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult> {
+        let table = &resource.resource;
+        let mut query = Vec::new();
+        if let Some(filter) = &params.filter {
+            query.extend(self.filter_params(table, filter));
+        }
+        if let Some(sort) = &params.sort {
+            query.push(self.order_param(sort));
+        }
+        let (records, total) = self
+            .fetch_page(table, query, params.pagination.as_ref())
+            .await?;
+        let total = total.unwrap_or(records.len());
+
+        Ok(GetListResult {
+            data: records,
+            total: Some(total),
+            page_info: params.pagination.map(|p| PageInfo::new(&p, total)),
+            meta: None,
+        })
+    }
+
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult> {
+        let table = &resource.resource;
+        let request = self
+            .request(Method::GET, table)?
+            .query(&[
+                self.id_filter(&params.id),
+                ("select".to_string(), self.select(table)),
+            ])
+            .header(ACCEPT, SINGLE_OBJECT);
+        let record = self.fetch_one(request).await?;
+        Ok(GetOneResult { data: record })
+    }
+
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult> {
+        let query = vec![self.ids_filter(&params.ids)];
+        let (records, _) = self.fetch_page(&resource.resource, query, None).await?;
+        Ok(GetManyResult { data: records })
+    }
+
+    // There are no nested routes, the target names the table and its
+    // foreign key column as `table.column`. Without a column the singular
+    // of the resource followed by `_id` is assumed, as in `post_id`.
+    async fn get_many_reference(
+        &self,
+        resource: Resource,
+        params: GetManyReferenceParams,
+    ) -> Result<GetManyReferenceResult> {
+        let (table, column) = match params.target.split_once('.') {
+            Some((table, column)) => (table.to_string(), column.to_string()),
+            None => (
+                params.target.clone(),
+                format!("{}_id", singular(&resource.resource)),
+            ),
+        };
+
+        let mut query = vec![(column, format!("eq.{}", params.id))];
+        query.extend(self.filter_params(&table, &params.filter));
+        query.push(self.order_param(&params.sort));
+        let (records, total) = self
+            .fetch_page(&table, query, Some(&params.pagination))
+            .await?;
+        let total = total.unwrap_or(records.len());
+
+        Ok(GetManyReferenceResult {
+            data: records,
+            total: Some(total),
+            page_info: Some(PageInfo::new(&params.pagination, total)),
+            meta: None,
+        })
+    }
+
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult> {
+        let table = &resource.resource;
+        let request = self
+            .request(Method::POST, table)?
+            .query(&[("select", self.select(table))])
+            .json(&params.data);
+        let record = self.fetch_one(self.returning(request, true)).await?;
+        Ok(CreateResult { data: record })
+    }
+
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult> {
+        let table = &resource.resource;
+        let request = self
+            .request(Method::PATCH, table)?
+            .query(&[
+                self.id_filter(&params.id),
+                ("select".to_string(), self.select(table)),
+            ])
+            .json(&params.data);
+        let record = self.fetch_one(self.returning(request, true)).await?;
+        Ok(UpdateResult { data: record })
+    }
+
+    async fn update_many(
+        &self,
+        resource: Resource,
+        params: UpdateManyParams,
+    ) -> Result<UpdateManyResult> {
+        let request = self
+            .request(Method::PATCH, &resource.resource)?
+            .json(&params.data);
+        let outcomes = self.write_many(request, params.ids, "updated").await;
+        Ok(UpdateManyResult { data: outcomes })
+    }
+
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult> {
+        let request = self
+            .request(Method::DELETE, &resource.resource)?
+            .query(&[self.id_filter(&params.id)]);
+        let record = self.fetch_one(self.returning(request, true)).await?;
+        Ok(DeleteResult {
+            data: params.previous_data.unwrap_or(record),
+        })
+    }
+
+    async fn delete_many(
+        &self,
+        resource: Resource,
+        params: DeleteManyParams,
+    ) -> Result<DeleteManyResult> {
+        let request = self.request(Method::DELETE, &resource.resource)?;
+        let outcomes = self.write_many(request, params.ids, "deleted").await;
+        Ok(DeleteManyResult { data: outcomes })
+    }
+}
+
+// Total of a counted request, from a `Content-Range` like `0-24/312`.
+fn content_range_total(headers: &HeaderMap<HeaderValue>) -> Option<usize> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .split_once('/')?
+        .1
+        .parse()
+        .ok()
+}
+
+// Escapes the wildcards of a `like` pattern. PostgREST turns every `*` into
+// `%`, so a literal one is matched by `_` instead.
+fn like_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+        .replace('*', "_")
+}
+
+// Quotes a value of an `in.(...)` or `or=(...)` list when it holds one of
+// the characters reserved by the list syntax.
+fn list_value(value: &str) -> String {
+    if value.contains([',', '.', ':', '(', ')', '"', ' ']) {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
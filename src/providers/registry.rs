@@ -0,0 +1,69 @@
+use crate::prelude::*;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::config::{Clients, Config, ProviderKind};
+use crate::provider::DataProvider;
+use crate::providers::{
+    GraphqlProvider, InMemoryProvider, JsonFileProvider, JsonPlaceholder, PostgrestProvider,
+    RestProvider, SqliteProvider,
+};
+
+// Registry instantiates the data provider of each configured client by name.
+// Providers are built the first time they are asked for and kept, so a
+// client that fails to open does not stop the others and switching back to
+// a client finds it as it was left.
+#[derive(Default)]
+pub struct Registry {
+    clients: Clients,
+    providers: HashMap<String, Arc<dyn DataProvider>>,
+}
+
+impl Registry {
+    pub fn new(clients: Clients) -> Self {
+        Self {
+            clients,
+            providers: HashMap::new(),
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.clients
+            .clients
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+
+    pub fn client(&self, name: &str) -> Option<&Config> {
+        self.clients.get(name)
+    }
+
+    pub fn provider(&mut self, name: &str) -> Result<Arc<dyn DataProvider>> {
+        if let Some(provider) = self.providers.get(name) {
+            return Ok(provider.clone());
+        }
+
+        let config = self
+            .clients
+            .get(name)
+            .ok_or_else(|| Error::Unknown(format!("no client named {}", name)))?;
+        let provider = build(config)?;
+        self.providers.insert(name.to_string(), provider.clone());
+        Ok(provider)
+    }
+}
+
+// Builds the provider of the kind the client selects.
+pub fn build(config: &Config) -> Result<Arc<dyn DataProvider>> {
+    Ok(match config.provider() {
+        ProviderKind::JsonServer => Arc::new(JsonPlaceholder::new(&config.url, &config.http)?),
+        ProviderKind::Rest => Arc::new(RestProvider::new(config)?),
+        ProviderKind::Postgrest => Arc::new(PostgrestProvider::new(config)?),
+        ProviderKind::Graphql => Arc::new(GraphqlProvider::new(config)?),
+        ProviderKind::Sqlite => Arc::new(SqliteProvider::new(config)?),
+        ProviderKind::JsonFile => Arc::new(JsonFileProvider::new(config)?),
+        ProviderKind::Memory => Arc::new(InMemoryProvider::from_config(config)?),
+    })
+}
@@ -0,0 +1,536 @@
+use crate::prelude::*;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use url::Url;
+
+use crate::config::{Config, PaginationStyle, RestConfig, TotalLocation};
+use crate::provider::*;
+use crate::providers::http::{self, build_client, escape_regex, query_value};
+use crate::utils::json_path;
+
+// RestProvider talks to any REST API whose conventions are declared in the
+// `rest` section of the config, per client and per resource.
+pub struct RestProvider {
+    url: Url,
+    client: reqwest::Client,
+    // Requests in flight at once during bulk operations.
+    concurrency: usize,
+    // Client settings, used by resources without overrides.
+    rest: RestConfig,
+    // Client settings merged with the overrides of each resource.
+    resources: HashMap<String, RestConfig>,
+}
+
+impl RestProvider {
+    pub fn new(config: &Config) -> Result<Self> {
+        // Paths are joined to the base url, which must end with a slash to
+        // keep its last segment.
+        let mut url = Url::parse(&config.url)?;
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        let rest = config.rest.clone().unwrap_or_default();
+        let resources = config
+            .resources
+            .iter()
+            .filter_map(|r| {
+                let overrides = r.rest.as_ref()?;
+                Some((Resource::new(&r.resource).resource, rest.merge(overrides)))
+            })
+            .collect();
+
+        Ok(Self {
+            url,
+            client: build_client(&config.http)?,
+            concurrency: config.http.concurrency(),
+            rest,
+            resources,
+        })
+    }
+
+    fn rest(&self, resource: &Resource) -> &RestConfig {
+        self.resources.get(&resource.resource).unwrap_or(&self.rest)
+    }
+
+    // Fills the placeholders of a path template and joins it to the base url.
+    // The parts of a composite id are encoded one by one: `{id}` joins them
+    // with the id separator, `{id.<field>}` and `{id.<n>}` take one of them.
+    fn url(
+        &self,
+        rest: &RestConfig,
+        template: &str,
+        resource: &Resource,
+        id: Option<&Identifier>,
+        target: Option<&str>,
+    ) -> Result<Url> {
+        let mut path = template
+            .replace("{resource}", &resource.resource)
+            .replace("{target}", target.unwrap_or_default());
+        let parts = match id {
+            Some(id) => id_parts(rest, id)?,
+            None => Vec::new(),
+        };
+        let parts: Vec<String> = parts
+            .iter()
+            .map(|part| urlencoding::encode(part).into_owned())
+            .collect();
+        for (i, (field, part)) in rest.id_fields().iter().zip(&parts).enumerate() {
+            path = path
+                .replace(&format!("{{id.{}}}", field), part)
+                .replace(&format!("{{id.{}}}", i), part);
+        }
+        let path = path.replace("{id}", &parts.join(rest.id_separator()));
+        Ok(self.url.join(path.trim_start_matches('/'))?)
+    }
+
+    fn request(&self, method: &str, url: Url) -> Result<reqwest::RequestBuilder> {
+        let method = reqwest::Method::from_bytes(method.as_bytes())
+            .map_err(|_| Error::Unknown(format!("Invalid HTTP method {}", method)))?;
+        Ok(self.client.request(method, url))
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        http::send(&self.client, request).await
+    }
+
+    fn pagination_params(
+        &self,
+        rest: &RestConfig,
+        pagination: &PaginationPayload,
+    ) -> Vec<(String, String)> {
+        let param = |name: &Option<String>, default: &str| {
+            name.clone().unwrap_or_else(|| default.to_string())
+        };
+        match rest.pagination.unwrap_or_default() {
+            PaginationStyle::Page => vec![
+                (
+                    param(&rest.page_param, "_page"),
+                    pagination.page.to_string(),
+                ),
+                (
+                    param(&rest.per_page_param, "_limit"),
+                    pagination.per_page.to_string(),
+                ),
+            ],
+            PaginationStyle::Offset => {
+                let offset = pagination.page.saturating_sub(1) * pagination.per_page;
+                vec![
+                    (param(&rest.offset_param, "_start"), offset.to_string()),
+                    (
+                        param(&rest.limit_param, "_limit"),
+                        pagination.per_page.to_string(),
+                    ),
+                ]
+            }
+            PaginationStyle::None => Vec::new(),
+        }
+    }
+
+    fn sort_params(&self, rest: &RestConfig, sort: &SortPayload) -> Vec<(String, String)> {
+        let sort_param = rest.sort_param.as_deref().unwrap_or("_sort").to_string();
+        match (&rest.order_param, &rest.desc_prefix) {
+            (None, Some(prefix)) => {
+                let field = match sort.order {
+                    SortOrder::Asc => sort.field.clone(),
+                    SortOrder::Desc => format!("{}{}", prefix, sort.field),
+                };
+                vec![(sort_param, field)]
+            }
+            (order_param, _) => vec![
+                (sort_param, sort.field.clone()),
+                (
+                    order_param.as_deref().unwrap_or("_order").to_string(),
+                    sort.order.to_string(),
+                ),
+            ],
+        }
+    }
+
+    fn filter_params(&self, rest: &RestConfig, filters: &FilterPayload) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        for condition in &filters.conditions {
+            let field = &condition.field;
+            let param = |op: &str, value: &serde_json::Value| {
+                rest.filter_param(op, field)
+                    .map(|name| (name, query_value(value)))
+            };
+            // Without a parameter of their own, strict comparisons are
+            // expressed as json-server does, `gte`/`lte` plus `ne`.
+            let strict = |op: &str, loose: &str, v: &serde_json::Value| match param(op, v) {
+                Some(param) => vec![param],
+                None => [param(loose, v), param("ne", v)]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            };
+            match &condition.filter {
+                Filter::Eq(v) => params.extend(param("eq", v)),
+                Filter::Ne(v) => params.extend(param("ne", v)),
+                Filter::Gt(v) => params.extend(strict("gt", "gte", v)),
+                Filter::Gte(v) => params.extend(param("gte", v)),
+                Filter::Lt(v) => params.extend(strict("lt", "lte", v)),
+                Filter::Lte(v) => params.extend(param("lte", v)),
+                Filter::Range(from, to) => {
+                    params.extend(param("gte", from));
+                    params.extend(param("lte", to));
+                }
+                Filter::Contains(v) => {
+                    // The default `_like` takes a regular expression.
+                    let value = if rest.filters.contains_key("contains") {
+                        v.clone()
+                    } else {
+                        escape_regex(v)
+                    };
+                    params.extend(
+                        rest.filter_param("contains", field)
+                            .map(|name| (name, value)),
+                    )
+                }
+                Filter::In(values) => match &rest.in_separator {
+                    Some(separator) => {
+                        let values: Vec<String> = values.iter().map(query_value).collect();
+                        params.extend(
+                            rest.filter_param("in", field)
+                                .map(|name| (name, values.join(separator))),
+                        );
+                    }
+                    None => params.extend(values.iter().filter_map(|v| param("in", v))),
+                },
+            }
+        }
+        if let Some(q) = &filters.q {
+            let search_param = rest.search_param.as_deref().unwrap_or("q");
+            params.push((search_param.to_string(), q.clone()));
+        }
+        params
+    }
+
+    // Composite ids are asked for by the values of each of their fields,
+    // `get_many` drops the records of the other combinations.
+    fn ids_params(&self, rest: &RestConfig, ids: &[Identifier]) -> Result<Vec<(String, String)>> {
+        let fields = rest.id_fields();
+        let params = |name: String, values: Vec<String>| match &rest.in_separator {
+            Some(separator) => vec![(name, values.join(separator))],
+            None => values.into_iter().map(|v| (name.clone(), v)).collect(),
+        };
+        if fields.len() == 1 {
+            let ids_param = rest.ids_param.as_deref().unwrap_or("id").to_string();
+            return Ok(params(
+                ids_param,
+                ids.iter().map(|id| id.to_string()).collect(),
+            ));
+        }
+
+        let parts = ids
+            .iter()
+            .map(|id| id_parts(rest, id))
+            .collect::<Result<Vec<_>>>()?;
+        let mut query = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            let mut values: Vec<String> = Vec::new();
+            for part in &parts {
+                if !values.contains(&part[i]) {
+                    values.push(part[i].clone());
+                }
+            }
+            let name = rest
+                .filter_param("in", field)
+                .unwrap_or_else(|| field.clone());
+            query.extend(params(name, values));
+        }
+        Ok(query)
+    }
+
+    fn list_params(
+        &self,
+        rest: &RestConfig,
+        pagination: Option<&PaginationPayload>,
+        sort: Option<&SortPayload>,
+        filter: Option<&FilterPayload>,
+    ) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        if let Some(filter) = filter {
+            params.extend(self.filter_params(rest, filter));
+        }
+        if let Some(sort) = sort {
+            params.extend(self.sort_params(rest, sort));
+        }
+        if let Some(pagination) = pagination {
+            params.extend(self.pagination_params(rest, pagination));
+        }
+        params
+    }
+
+    // Fetches a list, reading the records and the total where the config
+    // says they are.
+    async fn fetch_list(
+        &self,
+        rest: &RestConfig,
+        method: &str,
+        mut url: Url,
+        params: Vec<(String, String)>,
+    ) -> Result<(Vec<Record>, Option<usize>)> {
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        let response = self.send(self.request(method, url)?).await?;
+        let total = match rest.total() {
+            TotalLocation::Header(name) => response
+                .headers()
+                .get(&name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok()),
+            TotalLocation::Body(_) => None,
+        };
+        let body: serde_json::Value = response.json().await?;
+
+        let records_path = rest.records_path.as_deref().unwrap_or_default();
+        let records = match json_path::lookup(&body, records_path) {
+            Some(serde_json::Value::Array(records)) => records
+                .iter()
+                .map(|r| self.record(rest, r.clone()))
+                .collect::<Result<Vec<_>>>()?,
+            _ => {
+                return Err(Error::Response(format!(
+                    "no records array at `{}`",
+                    records_path
+                )))
+            }
+        };
+
+        let total = match rest.total() {
+            TotalLocation::Body(path) => json_path::lookup(&body, &path).and_then(|v| {
+                v.as_u64()
+                    .map(|n| n as usize)
+                    .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            }),
+            TotalLocation::Header(_) => total,
+        };
+
+        Ok((records, total))
+    }
+
+    async fn fetch_one(
+        &self,
+        rest: &RestConfig,
+        request: reqwest::RequestBuilder,
+    ) -> Result<Record> {
+        let response = self.send(request).await?;
+        self.unwrap_record(rest, response.json().await?)
+    }
+
+    // Takes the record out of the envelope of a single record response.
+    fn unwrap_record(&self, rest: &RestConfig, body: serde_json::Value) -> Result<Record> {
+        let record_path = rest.record_path.as_deref().unwrap_or_default();
+        let record = json_path::lookup(&body, record_path)
+            .ok_or_else(|| Error::Response(format!("no record at `{}`", record_path)))?;
+        self.record(rest, record.clone())
+    }
+
+    fn record(&self, rest: &RestConfig, value: serde_json::Value) -> Result<Record> {
+        Record::from_json(value, &rest.id_fields(), rest.id_separator())
+    }
+}
+
+#[async_trait]
+impl DataProvider for RestProvider {
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.list;
+        let url = self.url(rest, &endpoint.path("{resource}"), &resource, None, None)?;
+        let query = self.list_params(
+            rest,
+            params.pagination.as_ref(),
+            params.sort.as_ref(),
+            params.filter.as_ref(),
+        );
+        let (records, total) = self
+            .fetch_list(rest, &endpoint.method("GET"), url, query)
+            .await?;
+        let total = total.unwrap_or(records.len());
+
+        Ok(GetListResult {
+            data: records,
+            total: Some(total),
+            page_info: params.pagination.map(|p| PageInfo::new(&p, total)),
+            meta: None,
+        })
+    }
+
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.one;
+        let path = endpoint.path("{resource}/{id}");
+        let url = self.url(rest, &path, &resource, Some(&params.id), None)?;
+        let request = self.request(&endpoint.method("GET"), url)?;
+        let record = self.fetch_one(rest, request).await?;
+        Ok(GetOneResult { data: record })
+    }
+
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.many;
+        let url = self.url(rest, &endpoint.path("{resource}"), &resource, None, None)?;
+        let query = self.ids_params(rest, &params.ids)?;
+        let (mut records, _) = self
+            .fetch_list(rest, &endpoint.method("GET"), url, query)
+            .await?;
+        if rest.id_fields().len() > 1 {
+            let ids: Vec<String> = params.ids.iter().map(|id| id.to_string()).collect();
+            records.retain(|record| ids.contains(&record.id.to_string()));
+        }
+        Ok(GetManyResult { data: records })
+    }
+
+    async fn get_many_reference(
+        &self,
+        resource: Resource,
+        params: GetManyReferenceParams,
+    ) -> Result<GetManyReferenceResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.many_reference;
+        let path = endpoint.path("{resource}/{id}/{target}");
+        let url = self.url(
+            rest,
+            &path,
+            &resource,
+            Some(&params.id),
+            Some(&params.target),
+        )?;
+        let query = self.list_params(
+            rest,
+            Some(&params.pagination),
+            Some(&params.sort),
+            Some(&params.filter),
+        );
+        let (records, total) = self
+            .fetch_list(rest, &endpoint.method("GET"), url, query)
+            .await?;
+        let total = total.unwrap_or(records.len());
+
+        Ok(GetManyReferenceResult {
+            data: records,
+            total: Some(total),
+            page_info: Some(PageInfo::new(&params.pagination, total)),
+            meta: None,
+        })
+    }
+
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.create;
+        let url = self.url(rest, &endpoint.path("{resource}"), &resource, None, None)?;
+        let request = self.request(&endpoint.method("POST"), url)?;
+        let record = self.fetch_one(rest, request.json(&params.data)).await?;
+        Ok(CreateResult { data: record })
+    }
+
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.update;
+        let path = endpoint.path("{resource}/{id}");
+        let url = self.url(rest, &path, &resource, Some(&params.id), None)?;
+        let request = self.request(&endpoint.method("PUT"), url)?;
+        let record = self.fetch_one(rest, request.json(&params.data)).await?;
+        Ok(UpdateResult { data: record })
+    }
+
+    // Bulk edits only carry the fields filled in, so unless another method
+    // is configured they are patched in instead of replacing the records.
+    async fn update_many(
+        &self,
+        resource: Resource,
+        params: UpdateManyParams,
+    ) -> Result<UpdateManyResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.update;
+        let path = endpoint.path("{resource}/{id}");
+        let method = endpoint.method("PATCH");
+        let (resource, data) = (&resource, &params.data);
+        let outcomes = stream::iter(params.ids)
+            .map(|id| {
+                let request = self
+                    .url(rest, &path, resource, Some(&id), None)
+                    .and_then(|url| self.request(&method, url))
+                    .map(|request| request.json(data));
+                async move {
+                    let result = async { http::send_one(&self.client, request?).await }.await;
+                    ItemOutcome { id, result }
+                }
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(UpdateManyResult { data: outcomes })
+    }
+
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.delete;
+        let path = endpoint.path("{resource}/{id}");
+        let url = self.url(rest, &path, &resource, Some(&params.id), None)?;
+        let response = self
+            .send(self.request(&endpoint.method("DELETE"), url)?)
+            .await?;
+        match params.previous_data {
+            Some(record) => Ok(DeleteResult { data: record }),
+            None => Ok(DeleteResult {
+                data: self.unwrap_record(rest, response.json().await?)?,
+            }),
+        }
+    }
+
+    async fn delete_many(
+        &self,
+        resource: Resource,
+        params: DeleteManyParams,
+    ) -> Result<DeleteManyResult> {
+        let rest = self.rest(&resource);
+        let endpoint = &rest.endpoints.delete;
+        let path = endpoint.path("{resource}/{id}");
+        let method = endpoint.method("DELETE");
+        let resource = &resource;
+        let outcomes = stream::iter(params.ids)
+            .map(|id| {
+                let request = self
+                    .url(rest, &path, resource, Some(&id), None)
+                    .and_then(|url| self.request(&method, url));
+                async move {
+                    let result = async { http::send_one(&self.client, request?).await }.await;
+                    ItemOutcome { id, result }
+                }
+            })
+            .buffered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(DeleteManyResult { data: outcomes })
+    }
+}
+
+// Splits an id into the values of the id fields.
+fn id_parts(rest: &RestConfig, id: &Identifier) -> Result<Vec<String>> {
+    let fields = rest.id_fields();
+    let id = id.to_string();
+    if fields.len() == 1 {
+        return Ok(vec![id]);
+    }
+
+    let parts: Vec<String> = id.split(rest.id_separator()).map(String::from).collect();
+    if parts.len() != fields.len() {
+        return Err(Error::InvalidField {
+            field: fields.join(", "),
+            message: format!(
+                "expected {} parts separated by `{}` in {}",
+                fields.len(),
+                rest.id_separator(),
+                id
+            ),
+        });
+    }
+    Ok(parts)
+}
@@ -0,0 +1,513 @@
+use crate::prelude::*;
+
+use async_trait::async_trait;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{self, Config, FieldType};
+use crate::provider::*;
+
+// SqliteProvider maps every resource to the table of the same name in a
+// local database file.
+pub struct SqliteProvider {
+    database: Arc<Database>,
+}
+
+struct Database {
+    connection: Mutex<Connection>,
+    id_field: String,
+    // Configured fields of each resource, to read back bools and JSON.
+    fields: HashMap<String, Vec<config::Field>>,
+}
+
+// WHERE clause and its parameters.
+struct Clause {
+    sql: String,
+    params: Vec<SqlValue>,
+}
+
+impl SqliteProvider {
+    pub fn new(config: &Config) -> Result<Self> {
+        let path = config.url.trim_start_matches("sqlite://");
+        let sqlite = config.sqlite.clone().unwrap_or_default();
+
+        let database = Database {
+            connection: Mutex::new(Connection::open(path)?),
+            id_field: sqlite.id_field.unwrap_or_else(|| "id".to_string()),
+            fields: config
+                .resources
+                .iter()
+                .map(|r| (Resource::new(&r.resource).resource, r.fields.clone()))
+                .collect(),
+        };
+        Ok(Self {
+            database: Arc::new(database),
+        })
+    }
+
+    // Runs `f` on the connection in the blocking pool, SQLite calls and the
+    // lock around them would otherwise stall a runtime worker.
+    async fn run<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Database, &Connection) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let database = self.database.clone();
+        tokio::task::spawn_blocking(move || {
+            let connection = database.connection()?;
+            f(&database, &connection)
+        })
+        .await
+        .map_err(|e| Error::Unknown(e.to_string()))?
+    }
+}
+
+impl Database {
+    fn connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| Error::Unknown("SQLite connection poisoned".to_string()))
+    }
+
+    fn columns(&self, connection: &Connection, table: &str) -> Result<Vec<String>> {
+        let mut statement = connection.prepare(&format!("PRAGMA table_info({})", quote(table)))?;
+        let columns = statement
+            .query_map([], |row| row.get::<_, String>("name"))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(columns)
+    }
+
+    fn where_clause(
+        &self,
+        connection: &Connection,
+        table: &str,
+        filters: &FilterPayload,
+    ) -> Result<Clause> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        for condition in &filters.conditions {
+            let column = quote(&condition.field);
+            let mut compare = |op: &str, value: &Value| {
+                conditions.push(format!("{} {} ?", column, op));
+                params.push(sql_value(value));
+            };
+            match &condition.filter {
+                Filter::Eq(v) => compare("=", v),
+                Filter::Ne(v) => compare("IS NOT", v),
+                Filter::Gt(v) => compare(">", v),
+                Filter::Gte(v) => compare(">=", v),
+                Filter::Lt(v) => compare("<", v),
+                Filter::Lte(v) => compare("<=", v),
+                Filter::Range(from, to) => {
+                    conditions.push(format!("{} BETWEEN ? AND ?", column));
+                    params.extend([sql_value(from), sql_value(to)]);
+                }
+                Filter::Contains(v) => {
+                    conditions.push(format!("{} LIKE ? ESCAPE '\\'", column));
+                    params.push(SqlValue::Text(like_pattern(v)));
+                }
+                Filter::In(values) => {
+                    let placeholders = vec!["?"; values.len()].join(", ");
+                    conditions.push(format!("{} IN ({})", column, placeholders));
+                    params.extend(values.iter().map(sql_value));
+                }
+            }
+        }
+
+        // The search matches any column of the table.
+        if let Some(q) = &filters.q {
+            let columns = self.columns(connection, table)?;
+            let search: Vec<String> = columns
+                .iter()
+                .map(|c| format!("{} LIKE ? ESCAPE '\\'", quote(c)))
+                .collect();
+            if !search.is_empty() {
+                conditions.push(format!("({})", search.join(" OR ")));
+                params.extend(columns.iter().map(|_| SqlValue::Text(like_pattern(q))));
+            }
+        }
+
+        let sql = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        Ok(Clause { sql, params })
+    }
+
+    // Selects one page of the rows matching `clause`, with the number of all
+    // of them.
+    fn select(
+        &self,
+        connection: &Connection,
+        table: &str,
+        clause: Clause,
+        sort: Option<&SortPayload>,
+        pagination: Option<&PaginationPayload>,
+    ) -> Result<(Vec<Record>, usize)> {
+        let count_sql = format!("SELECT COUNT(*) FROM {}{}", quote(table), clause.sql);
+        let total: usize =
+            connection.query_row(&count_sql, params_from_iter(&clause.params), |row| {
+                row.get(0)
+            })?;
+
+        let mut sql = format!("SELECT * FROM {}{}", quote(table), clause.sql);
+        if let Some(sort) = sort {
+            let order = sort.order.to_string().to_uppercase();
+            sql.push_str(&format!(" ORDER BY {} {}", quote(&sort.field), order));
+        }
+        if let Some(pagination) = pagination {
+            let offset = pagination.page.saturating_sub(1) * pagination.per_page;
+            sql.push_str(&format!(" LIMIT {} OFFSET {}", pagination.per_page, offset));
+        }
+
+        let records = self.query(connection, table, &sql, clause.params)?;
+        Ok((records, total))
+    }
+
+    fn query(
+        &self,
+        connection: &Connection,
+        table: &str,
+        sql: &str,
+        params: Vec<SqlValue>,
+    ) -> Result<Vec<Record>> {
+        let mut statement = connection.prepare(sql)?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let fields = self.fields.get(table);
+
+        let mut rows = statement.query(params_from_iter(params))?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut object = serde_json::Map::new();
+            for (i, column) in columns.iter().enumerate() {
+                let field = fields.and_then(|f| f.iter().find(|f| f.name == *column));
+                object.insert(column.clone(), json_value(row.get_ref(i)?, field));
+            }
+            records.push(Record::from_json(
+                Value::Object(object),
+                std::slice::from_ref(&self.id_field),
+                "",
+            )?);
+        }
+        Ok(records)
+    }
+
+    // Runs a statement ending with `RETURNING *` on the record `id`.
+    fn returning_one(
+        &self,
+        connection: &Connection,
+        table: &str,
+        sql: &str,
+        params: Vec<SqlValue>,
+        id: Option<&Identifier>,
+    ) -> Result<Record> {
+        let mut records = self.query(connection, table, sql, params)?;
+        match records.pop() {
+            Some(record) => Ok(record),
+            None => Err(match id {
+                Some(id) => Error::NotFound {
+                    resource: table.to_string(),
+                    id: id.to_string(),
+                },
+                None => Error::Response(format!("nothing written to {}", table)),
+            }),
+        }
+    }
+
+    fn select_one(&self, connection: &Connection, table: &str, id: &Identifier) -> Result<Record> {
+        let sql = format!(
+            "SELECT * FROM {} WHERE {} = ?",
+            quote(table),
+            quote(&self.id_field),
+        );
+        self.returning_one(connection, table, &sql, vec![id_value(id)], Some(id))
+    }
+
+    // Without any column to set there is nothing to write, the row is
+    // returned as it is.
+    fn update_one(
+        &self,
+        connection: &Connection,
+        table: &str,
+        id: &Identifier,
+        data: &HashMap<String, Value>,
+    ) -> Result<Record> {
+        if data.is_empty() {
+            return self.select_one(connection, table, id);
+        }
+        let assignments: Vec<String> = data.keys().map(|k| format!("{} = ?", quote(k))).collect();
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {} = ? RETURNING *",
+            quote(table),
+            assignments.join(", "),
+            quote(&self.id_field),
+        );
+        let params = data
+            .values()
+            .map(sql_value)
+            .chain(std::iter::once(id_value(id)))
+            .collect();
+        self.returning_one(connection, table, &sql, params, Some(id))
+    }
+
+    fn delete_one(&self, connection: &Connection, table: &str, id: &Identifier) -> Result<Record> {
+        let sql = format!(
+            "DELETE FROM {} WHERE {} = ? RETURNING *",
+            quote(table),
+            quote(&self.id_field),
+        );
+        self.returning_one(connection, table, &sql, vec![id_value(id)], Some(id))
+    }
+
+    // Column of `target` referencing `table`, declared as a foreign key or
+    // named after the singular of the table, as in `post_id`.
+    fn foreign_key(&self, connection: &Connection, target: &str, table: &str) -> Result<String> {
+        let mut statement =
+            connection.prepare(&format!("PRAGMA foreign_key_list({})", quote(target)))?;
+        let keys = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>("table")?,
+                    row.get::<_, String>("from")?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(keys
+            .into_iter()
+            .find(|(referenced, _)| referenced == table)
+            .map(|(_, column)| column)
+            .unwrap_or_else(|| format!("{}_id", singular(table))))
+    }
+}
+
+#[async_trait]
+impl DataProvider for SqliteProvider {
+    async fn get_list(&self, resource: Resource, params: GetListParams) -> Result<GetListResult> {
+        self.run(move |db, connection| {
+            let table = &resource.resource;
+            let filter = params.filter.unwrap_or_default();
+            let clause = db.where_clause(connection, table, &filter)?;
+            let (records, total) = db.select(
+                connection,
+                table,
+                clause,
+                params.sort.as_ref(),
+                params.pagination.as_ref(),
+            )?;
+
+            Ok(GetListResult {
+                data: records,
+                total: Some(total),
+                page_info: params.pagination.map(|p| PageInfo::new(&p, total)),
+                meta: None,
+            })
+        })
+        .await
+    }
+
+    async fn get_one(&self, resource: Resource, params: GetOneParams) -> Result<GetOneResult> {
+        self.run(move |db, connection| {
+            let record = db.select_one(connection, &resource.resource, &params.id)?;
+            Ok(GetOneResult { data: record })
+        })
+        .await
+    }
+
+    async fn get_many(&self, resource: Resource, params: GetManyParams) -> Result<GetManyResult> {
+        self.run(move |db, connection| {
+            let table = &resource.resource;
+            let placeholders = vec!["?"; params.ids.len()].join(", ");
+            let sql = format!(
+                "SELECT * FROM {} WHERE {} IN ({})",
+                quote(table),
+                quote(&db.id_field),
+                placeholders,
+            );
+            let ids = params.ids.iter().map(id_value).collect();
+            let records = db.query(connection, table, &sql, ids)?;
+            Ok(GetManyResult { data: records })
+        })
+        .await
+    }
+
+    async fn get_many_reference(
+        &self,
+        resource: Resource,
+        params: GetManyReferenceParams,
+    ) -> Result<GetManyReferenceResult> {
+        self.run(move |db, connection| {
+            let (target, column) = match params.target.split_once('.') {
+                Some((target, column)) => (target.to_string(), column.to_string()),
+                None => {
+                    let column = db.foreign_key(connection, &params.target, &resource.resource)?;
+                    (params.target.clone(), column)
+                }
+            };
+
+            let mut clause = db.where_clause(connection, &target, &params.filter)?;
+            let reference = format!("{} = ?", quote(&column));
+            clause.sql = if clause.sql.is_empty() {
+                format!(" WHERE {}", reference)
+            } else {
+                format!("{} AND {}", clause.sql, reference)
+            };
+            clause.params.push(id_value(&params.id));
+
+            let (records, total) = db.select(
+                connection,
+                &target,
+                clause,
+                Some(&params.sort),
+                Some(&params.pagination),
+            )?;
+
+            Ok(GetManyReferenceResult {
+                data: records,
+                total: Some(total),
+                page_info: Some(PageInfo::new(&params.pagination, total)),
+                meta: None,
+            })
+        })
+        .await
+    }
+
+    async fn create(&self, resource: Resource, params: CreateParams) -> Result<CreateResult> {
+        self.run(move |db, connection| {
+            let table = &resource.resource;
+            let sql = if params.data.is_empty() {
+                format!("INSERT INTO {} DEFAULT VALUES RETURNING *", quote(table))
+            } else {
+                let columns: Vec<String> = params.data.keys().map(|k| quote(k)).collect();
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({}) RETURNING *",
+                    quote(table),
+                    columns.join(", "),
+                    vec!["?"; columns.len()].join(", "),
+                )
+            };
+            let values = params.data.values().map(sql_value).collect();
+            let record = db.returning_one(connection, table, &sql, values, None)?;
+            Ok(CreateResult { data: record })
+        })
+        .await
+    }
+
+    async fn update(&self, resource: Resource, params: UpdateParams) -> Result<UpdateResult> {
+        self.run(move |db, connection| {
+            let record = db.update_one(connection, &resource.resource, &params.id, &params.data)?;
+            Ok(UpdateResult { data: record })
+        })
+        .await
+    }
+
+    async fn update_many(
+        &self,
+        resource: Resource,
+        params: UpdateManyParams,
+    ) -> Result<UpdateManyResult> {
+        self.run(move |db, connection| {
+            let outcomes = params
+                .ids
+                .into_iter()
+                .map(|id| {
+                    let result = db
+                        .update_one(connection, &resource.resource, &id, &params.data)
+                        .map(|_| ());
+                    ItemOutcome { id, result }
+                })
+                .collect();
+            Ok(UpdateManyResult { data: outcomes })
+        })
+        .await
+    }
+
+    async fn delete(&self, resource: Resource, params: DeleteParams) -> Result<DeleteResult> {
+        self.run(move |db, connection| {
+            let record = db.delete_one(connection, &resource.resource, &params.id)?;
+            Ok(DeleteResult { data: record })
+        })
+        .await
+    }
+
+    async fn delete_many(
+        &self,
+        resource: Resource,
+        params: DeleteManyParams,
+    ) -> Result<DeleteManyResult> {
+        self.run(move |db, connection| {
+            let outcomes = params
+                .ids
+                .into_iter()
+                .map(|id| {
+                    let result = db
+                        .delete_one(connection, &resource.resource, &id)
+                        .map(|_| ());
+                    ItemOutcome { id, result }
+                })
+                .collect();
+            Ok(DeleteManyResult { data: outcomes })
+        })
+        .await
+    }
+}
+
+// Quotes a table or column name.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Matches `value` anywhere, `%` and `_` in it taken literally.
+fn like_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn id_value(id: &Identifier) -> SqlValue {
+    match id {
+        Identifier::Num(n) => SqlValue::Integer(*n as i64),
+        Identifier::Str(s) => SqlValue::Text(s.clone()),
+    }
+}
+
+// Bools are stored as integers, arrays and objects as JSON text.
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+// Reads a column back, turning it into a bool or parsed JSON when the field
+// is configured so.
+fn json_value(value: ValueRef<'_>, field: Option<&config::Field>) -> Value {
+    let kind = field.map(|f| f.kind).unwrap_or_default();
+    match (value, kind) {
+        (ValueRef::Null, _) => Value::Null,
+        (ValueRef::Integer(i), FieldType::Bool) => Value::Bool(i != 0),
+        (ValueRef::Integer(i), _) => Value::from(i),
+        (ValueRef::Real(f), _) => Value::from(f),
+        (ValueRef::Text(text), FieldType::Json) => {
+            let text = String::from_utf8_lossy(text);
+            serde_json::from_str(&text).unwrap_or(Value::String(text.into_owned()))
+        }
+        (ValueRef::Text(text), _) => Value::String(String::from_utf8_lossy(text).into_owned()),
+        (ValueRef::Blob(blob), _) => Value::String(format!("<{} bytes>", blob.len())),
+    }
+}
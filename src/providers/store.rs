@@ -0,0 +1,320 @@
+use crate::prelude::*;
+
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::provider::*;
+use crate::utils::json_path;
+
+// Store holds collections of JSON records in memory, shaped like a
+// json-server `db.json`, and answers queries on them.
+#[derive(Debug, Default, Clone)]
+pub struct Store {
+    collections: BTreeMap<String, Vec<Value>>,
+    // Top-level keys that are not collections, kept to be written back.
+    others: Map<String, Value>,
+}
+
+impl Store {
+    // Reads `{"posts": [{"id": 1, ...}], ...}`. Only arrays are collections
+    // and only their objects are records.
+    pub fn from_value(value: Value) -> Result<Self> {
+        let Value::Object(db) = value else {
+            return Err(Error::Response(
+                "expected an object of collections".to_string(),
+            ));
+        };
+
+        let mut store = Self::default();
+        for (name, value) in db {
+            match value {
+                Value::Array(rows) => {
+                    store.collections.insert(name, rows);
+                }
+                other => {
+                    store.others.insert(name, other);
+                }
+            }
+        }
+        Ok(store)
+    }
+
+    pub fn from_records(collections: HashMap<String, Vec<Record>>) -> Self {
+        let collections = collections
+            .into_iter()
+            .map(|(name, records)| (name, records.into_iter().map(row).collect()))
+            .collect();
+        Self {
+            collections,
+            others: Map::new(),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut db = self.others.clone();
+        for (name, rows) in &self.collections {
+            db.insert(name.clone(), Value::Array(rows.clone()));
+        }
+        Value::Object(db)
+    }
+
+    fn rows(&self, resource: &str) -> &[Value] {
+        self.collections
+            .get(resource)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn position(&self, resource: &str, id: &Identifier) -> Result<usize> {
+        self.rows(resource)
+            .iter()
+            .position(|row| has_id(row, id))
+            .ok_or_else(|| not_found(resource, id))
+    }
+
+    // Filters, sorts and paginates a collection, returning the page and the
+    // number of records matching the filter.
+    pub fn list(
+        &self,
+        resource: &str,
+        filter: Option<&FilterPayload>,
+        sort: Option<&SortPayload>,
+        pagination: Option<&PaginationPayload>,
+    ) -> Result<(Vec<Record>, usize)> {
+        let mut rows: Vec<&Value> = self
+            .rows(resource)
+            .iter()
+            .filter(|row| row.is_object())
+            .filter(|row| filter.is_none_or(|f| matches(row, f)))
+            .collect();
+        let total = rows.len();
+
+        if let Some(sort) = sort {
+            rows.sort_by(|a, b| {
+                let ordering = compare(field(a, &sort.field), field(b, &sort.field));
+                match sort.order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            });
+        }
+
+        let rows: Vec<&Value> = match pagination {
+            Some(p) => rows
+                .into_iter()
+                .skip(p.page.saturating_sub(1) * p.per_page)
+                .take(p.per_page)
+                .collect(),
+            None => rows,
+        };
+
+        let records = rows
+            .into_iter()
+            .map(|row| record(row.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((records, total))
+    }
+
+    // The target names the related collection and its foreign key field as
+    // `collection.field`, `postId` for `posts` when there is no field.
+    pub fn many_reference(
+        &self,
+        resource: &str,
+        params: &GetManyReferenceParams,
+    ) -> Result<(Vec<Record>, usize)> {
+        let (target, key) = match params.target.split_once('.') {
+            Some((target, key)) => (target.to_string(), key.to_string()),
+            None => (params.target.clone(), foreign_key(resource)),
+        };
+        let id = Value::String(params.id.to_string());
+        let mut filter = FilterPayload::new().with(&key, Filter::Eq(id));
+        filter.merge(params.filter.clone());
+
+        self.list(
+            &target,
+            Some(&filter),
+            Some(&params.sort),
+            Some(&params.pagination),
+        )
+    }
+
+    pub fn one(&self, resource: &str, id: &Identifier) -> Result<Record> {
+        let position = self.position(resource, id)?;
+        record(self.rows(resource)[position].clone())
+    }
+
+    pub fn many(&self, resource: &str, ids: &[Identifier]) -> Result<Vec<Record>> {
+        self.rows(resource)
+            .iter()
+            .filter(|row| ids.iter().any(|id| has_id(row, id)))
+            .map(|row| record(row.clone()))
+            .collect()
+    }
+
+    // Inserts a record, numbered after the highest numeric id unless the
+    // data carries its own.
+    pub fn create(&mut self, resource: &str, data: HashMap<String, Value>) -> Result<Record> {
+        let mut new: Map<String, Value> = data.into_iter().collect();
+        match new.get("id").filter(|id| !id.is_null()) {
+            Some(id) => {
+                let id = Identifier::try_from(id)?;
+                if self.position(resource, &id).is_ok() {
+                    return Err(Error::InvalidField {
+                        field: "id".to_string(),
+                        message: format!("{} is already taken", id),
+                    });
+                }
+            }
+            None => {
+                let next = self
+                    .rows(resource)
+                    .iter()
+                    .filter_map(|row| row.get("id").and_then(Value::as_u64))
+                    .max()
+                    .map_or(1, |max| max + 1);
+                new.insert("id".to_string(), Value::from(next));
+            }
+        }
+
+        let rows = self.collections.entry(resource.to_string()).or_default();
+        rows.push(Value::Object(new.clone()));
+        record(Value::Object(new))
+    }
+
+    // Merges `data` into the record, its id left untouched.
+    pub fn update(
+        &mut self,
+        resource: &str,
+        id: &Identifier,
+        data: &HashMap<String, Value>,
+    ) -> Result<Record> {
+        let position = self.position(resource, id)?;
+        let rows = self.collections.entry(resource.to_string()).or_default();
+        if let Value::Object(row) = &mut rows[position] {
+            for (key, value) in data {
+                if key != "id" {
+                    row.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        record(rows[position].clone())
+    }
+
+    pub fn delete(&mut self, resource: &str, id: &Identifier) -> Result<Record> {
+        let position = self.position(resource, id)?;
+        let rows = self.collections.entry(resource.to_string()).or_default();
+        record(rows.remove(position))
+    }
+}
+
+// Field of a related collection referencing `collection`, as json-server
+// names it: `postId` for `posts`.
+pub fn foreign_key(collection: &str) -> String {
+    format!("{}Id", singular(collection))
+}
+
+fn record(row: Value) -> Result<Record> {
+    Record::from_json(row, &["id".to_string()], "")
+}
+
+fn row(record: Record) -> Value {
+    let id = match record.id {
+        Identifier::Num(n) => Value::from(n),
+        Identifier::Str(s) => Value::String(s),
+    };
+    let fields = record.fields.into_iter();
+    Value::Object(
+        std::iter::once(("id".to_string(), id))
+            .chain(fields)
+            .collect(),
+    )
+}
+
+fn not_found(resource: &str, id: &Identifier) -> Error {
+    Error::NotFound {
+        resource: resource.to_string(),
+        id: id.to_string(),
+    }
+}
+
+// Ids match whatever their JSON type, as `"3"` typed in a filter and `3`.
+fn has_id(row: &Value, id: &Identifier) -> bool {
+    row.get("id").is_some_and(|v| text(v) == id.to_string())
+}
+
+// Value of a field, following dotted paths into nested objects.
+fn field<'a>(row: &'a Value, name: &str) -> &'a Value {
+    json_path::lookup(row, name).unwrap_or(&Value::Null)
+}
+
+fn matches(row: &Value, filter: &FilterPayload) -> bool {
+    let conditions = filter.conditions.iter().all(|condition| {
+        let value = field(row, &condition.field);
+        let is = |operand: &Value, expected: &[Ordering]| {
+            !value.is_null() && expected.contains(&compare(value, operand))
+        };
+        match &condition.filter {
+            Filter::Eq(v) => equals(value, v),
+            Filter::Ne(v) => !equals(value, v),
+            Filter::Gt(v) => is(v, &[Ordering::Greater]),
+            Filter::Gte(v) => is(v, &[Ordering::Greater, Ordering::Equal]),
+            Filter::Lt(v) => is(v, &[Ordering::Less]),
+            Filter::Lte(v) => is(v, &[Ordering::Less, Ordering::Equal]),
+            Filter::Range(from, to) => {
+                is(from, &[Ordering::Greater, Ordering::Equal])
+                    && is(to, &[Ordering::Less, Ordering::Equal])
+            }
+            Filter::Contains(v) => contains(value, v),
+            Filter::In(values) => values.iter().any(|v| equals(value, v)),
+        }
+    });
+
+    // The search matches any field, nested ones included.
+    let search = filter.q.as_ref().is_none_or(|q| {
+        row.as_object()
+            .into_iter()
+            .flatten()
+            .any(|(_, v)| contains(v, q))
+    });
+
+    conditions && search
+}
+
+fn equals(value: &Value, operand: &Value) -> bool {
+    compare(value, operand) == Ordering::Equal
+}
+
+fn contains(value: &Value, needle: &str) -> bool {
+    text(value).to_lowercase().contains(&needle.to_lowercase())
+}
+
+// Orders values of the same JSON type naturally, numbers given as text
+// included. Anything else is compared by its text, nulls first.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => match (number(a), number(b)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            _ => text(a).cmp(&text(b)),
+        },
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,960 @@
+use crate::prelude::*;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::widgets::{ListState, TableState};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::config::{self, Config, FieldType};
+use crate::provider::*;
+use crate::providers::Registry;
+use crate::tui::detail::Detail;
+use crate::tui::filter_bar::{FilterAction, FilterEditor};
+use crate::tui::form::{Form, FormAction, FormMode};
+
+// Operation on several records at once, kept around to be retried on the
+// ids that failed.
+#[derive(Debug, Clone)]
+pub enum BulkAction {
+    Delete(Vec<Identifier>),
+    Update(Vec<Identifier>, HashMap<String, serde_json::Value>),
+}
+
+impl BulkAction {
+    pub fn ids(&self) -> &[Identifier] {
+        match self {
+            BulkAction::Delete(ids) | BulkAction::Update(ids, _) => ids,
+        }
+    }
+
+    fn with_ids(&self, ids: Vec<Identifier>) -> Self {
+        match self {
+            BulkAction::Delete(_) => BulkAction::Delete(ids),
+            BulkAction::Update(_, data) => BulkAction::Update(ids, data.clone()),
+        }
+    }
+}
+
+// Answer of a provider call made in the background. Answers tagged with a
+// request number are dropped when a newer request of their kind was made.
+enum Message {
+    List(u64, Result<GetListResult>),
+    References(u64, config::Field, Result<GetManyResult>),
+    Detail(u64, Vec<config::Relation>, Result<GetOneResult>),
+    // The tab the records belong to, 1 being the first relation.
+    Related(u64, usize, Result<GetManyReferenceResult>),
+    // Writes carry the selection of the resource they were made on, and its
+    // name.
+    Saved(u64, String, Result<(&'static str, Record)>),
+    Bulk(
+        u64,
+        String,
+        BulkAction,
+        &'static str,
+        Result<Vec<ItemOutcome>>,
+    ),
+}
+
+// Which pane receives the navigation keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Sidebar,
+    Table,
+}
+
+// App holds the whole state of the UI between frames.
+pub struct App {
+    pub config: Config,
+    pub focus: Focus,
+    pub sidebar_state: ListState,
+    pub table_state: TableState,
+    pub records: Vec<Record>,
+    pub total: Option<usize>,
+    pub page: usize,
+    pub page_info: Option<PageInfo>,
+    pub sort: Option<SortPayload>,
+    pub filter: FilterPayload,
+    pub filter_editor: Option<FilterEditor>,
+    // Column of the table header the sort key acts on, 0 being the id.
+    pub column: usize,
+    // Labels of referenced records, by field name and then by id.
+    pub references: HashMap<String, HashMap<String, String>>,
+    pub detail: Option<Detail>,
+    pub form: Option<Form>,
+    // Rows marked for bulk operations.
+    pub marked: Vec<Identifier>,
+    // Destructive action waiting for the user to confirm it.
+    pub confirm: Option<BulkAction>,
+    // Last bulk action restricted to the ids that failed.
+    pub retry: Option<BulkAction>,
+    pub status: Option<String>,
+    pub should_quit: bool,
+    // Client picker, open when set.
+    pub picker: Option<ListState>,
+    pub registry: Registry,
+    // Provider calls in flight.
+    pub pending: usize,
+    provider: Arc<dyn DataProvider>,
+    sender: UnboundedSender<Message>,
+    receiver: UnboundedReceiver<Message>,
+    // Latest list, detail and related requests.
+    list_request: u64,
+    detail_request: u64,
+    related_request: u64,
+    // Latest selection of a resource.
+    resource_request: u64,
+}
+
+impl App {
+    pub fn new(config: Config, provider: Arc<dyn DataProvider>) -> Self {
+        let mut sidebar_state = ListState::default();
+        if !config.resources.is_empty() {
+            sidebar_state.select(Some(0));
+        }
+        let filter = config
+            .resources
+            .first()
+            .map(|r| r.filter.clone())
+            .unwrap_or_default();
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        Self {
+            config,
+            focus: Focus::Sidebar,
+            sidebar_state,
+            table_state: TableState::default(),
+            records: Vec::new(),
+            total: None,
+            page: 1,
+            page_info: None,
+            sort: None,
+            filter,
+            filter_editor: None,
+            column: 0,
+            references: HashMap::new(),
+            detail: None,
+            form: None,
+            marked: Vec::new(),
+            confirm: None,
+            retry: None,
+            status: None,
+            should_quit: false,
+            picker: None,
+            registry: Registry::default(),
+            pending: 0,
+            provider,
+            sender,
+            receiver,
+            list_request: 0,
+            detail_request: 0,
+            related_request: 0,
+            resource_request: 0,
+        }
+    }
+
+    pub fn with_registry(mut self, registry: Registry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    // Opens the client picker on the current client.
+    pub fn open_picker(&mut self) {
+        let names = self.registry.names();
+        let current = names.iter().position(|n| *n == self.config.name);
+        let mut state = ListState::default();
+        state.select(current.or((!names.is_empty()).then_some(0)));
+        self.picker = Some(state);
+    }
+
+    fn handle_picker_key(&mut self, key: KeyEvent) {
+        let Some(picker) = &mut self.picker else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.picker = None,
+            KeyCode::Down | KeyCode::Char('j') => picker.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => picker.select_previous(),
+            KeyCode::Enter => {
+                let names = self.registry.names();
+                let name = picker
+                    .selected()
+                    .and_then(|i| names.get(i.min(names.len().saturating_sub(1))))
+                    .map(|n| n.to_string());
+                self.picker = None;
+                if let Some(name) = name {
+                    self.switch_client(&name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Starts over on another client of the registry. The state of the
+    // current one is dropped, answers still due to it included, as they
+    // are sent to the channel going away with it.
+    pub fn switch_client(&mut self, name: &str) {
+        if name == self.config.name {
+            return;
+        }
+        let (provider, config) = match self.registry.provider(name) {
+            Ok(provider) => (provider, self.registry.client(name).cloned()),
+            Err(e) => {
+                self.status = Some(format!("Error opening {}: {}", name, e));
+                return;
+            }
+        };
+        let Some(config) = config else {
+            return;
+        };
+
+        let registry = std::mem::take(&mut self.registry);
+        *self = App::new(config, provider).with_registry(registry);
+        self.load_list();
+    }
+
+    // Runs a provider call in the background, its answer coming back
+    // through `receive` so the UI keeps responding meanwhile.
+    fn spawn<F>(&mut self, call: impl FnOnce(Arc<dyn DataProvider>) -> F)
+    where
+        F: Future<Output = Message> + Send + 'static,
+    {
+        self.pending += 1;
+        let sender = self.sender.clone();
+        let call = call(self.provider.clone());
+        tokio::spawn(async move {
+            // The receiver lives as long as the app, nobody is left to
+            // tell once it is gone.
+            let _ = sender.send(call.await);
+        });
+    }
+
+    // Applies the answers of the calls completed since the last frame.
+    pub fn receive(&mut self) {
+        while let Ok(message) = self.receiver.try_recv() {
+            self.pending = self.pending.saturating_sub(1);
+            self.apply(message);
+        }
+    }
+
+    fn apply(&mut self, message: Message) {
+        match message {
+            Message::List(request, result) if request == self.list_request => {
+                self.apply_list(result)
+            }
+            Message::References(request, field, result) if request == self.list_request => {
+                self.apply_references(field, result)
+            }
+            Message::Detail(request, relations, result) if request == self.detail_request => {
+                self.apply_detail(relations, result)
+            }
+            Message::Related(request, tab, result) if request == self.related_request => {
+                self.apply_related(tab, result)
+            }
+            Message::Saved(request, _, result) if request == self.resource_request => {
+                self.apply_saved(result)
+            }
+            Message::Bulk(request, _, action, verb, result) if request == self.resource_request => {
+                self.apply_bulk(action, verb, result)
+            }
+            // Writes on a resource left meanwhile are only reported, their
+            // records are not on screen anymore.
+            Message::Saved(_, resource, result) => {
+                let report =
+                    result.map(|(action, record)| format!("{} record {}", action, record.id()));
+                self.report_write(&resource, report)
+            }
+            Message::Bulk(_, resource, _, verb, result) => {
+                let report = result.map(|outcomes| summarize(&outcomes, verb));
+                self.report_write(&resource, report)
+            }
+            _ => {}
+        }
+    }
+
+    fn report_write(&mut self, resource: &str, report: Result<String>) {
+        self.status = Some(match report {
+            Ok(report) => format!("{} in {}", report, resource),
+            Err(e) => format!("Error writing to {}: {}", resource, e),
+        });
+    }
+
+    pub fn selected_resource(&self) -> Option<&config::Resource> {
+        self.sidebar_state
+            .selected()
+            .and_then(|i| self.config.resources.get(i))
+    }
+
+    pub fn selected_record(&self) -> Option<&Record> {
+        self.table_state
+            .selected()
+            .and_then(|i| self.records.get(i))
+    }
+
+    // Fetches the records of the selected resource through the provider.
+    pub fn load_list(&mut self) {
+        let Some(resource) = self.selected_resource() else {
+            return;
+        };
+        let resource_name = Resource::new(&resource.resource);
+
+        let params = GetListParams {
+            pagination: Some(PaginationPayload {
+                page: self.page,
+                per_page: resource.per_page,
+            }),
+            sort: self.sort.clone(),
+            filter: (!self.filter.is_empty()).then(|| self.filter.clone()),
+            meta: None,
+        };
+
+        self.list_request += 1;
+        self.status = None;
+        let request = self.list_request;
+        self.spawn(move |provider| async move {
+            Message::List(request, provider.get_list(resource_name, params).await)
+        });
+    }
+
+    fn apply_list(&mut self, result: Result<GetListResult>) {
+        match result {
+            Ok(result) => {
+                self.total = result.total;
+                self.page_info = result.page_info;
+                self.records = result.data;
+                self.table_state.select(if self.records.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                });
+                self.load_references();
+            }
+            Err(e) => {
+                self.records.clear();
+                self.total = None;
+                self.page_info = None;
+                self.references.clear();
+                self.table_state.select(None);
+                self.status = Some(format!("Error fetching list: {}", e));
+            }
+        }
+    }
+
+    // Sorts the list by the selected column, or flips the order when it is
+    // already sorted by it.
+    pub fn sort_by_column(&mut self) {
+        let Some(resource) = self.selected_resource() else {
+            return;
+        };
+        let field = match self.column {
+            0 => "id".to_string(),
+            i => match resource.fields.get(i - 1) {
+                Some(f) => f.name.clone(),
+                None => return,
+            },
+        };
+
+        self.sort = match self.sort.take() {
+            Some(sort) if sort.field == field => Some(SortPayload {
+                field,
+                order: sort.order.toggle(),
+            }),
+            _ => Some(SortPayload {
+                field,
+                order: SortOrder::Asc,
+            }),
+        };
+        self.page = 1;
+        self.load_list();
+    }
+
+    // Removes the last filter chip, the search being the first one.
+    pub fn remove_last_filter(&mut self) {
+        if self.filter.conditions.pop().is_none() && self.filter.q.take().is_none() {
+            return;
+        }
+        self.page = 1;
+        self.load_list();
+    }
+
+    // Restores the filter declared for the resource in the config.
+    pub fn reset_filter(&mut self) {
+        self.filter = self
+            .selected_resource()
+            .map(|r| r.filter.clone())
+            .unwrap_or_default();
+        self.page = 1;
+        self.load_list();
+    }
+
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        let Some(editor) = &mut self.filter_editor else {
+            return;
+        };
+
+        match editor.handle_key(key) {
+            Ok(FilterAction::None) => {}
+            Ok(FilterAction::Cancel) => self.filter_editor = None,
+            Ok(FilterAction::Add(filter)) => {
+                self.filter_editor = None;
+                self.filter.merge(filter);
+                self.page = 1;
+                self.load_list();
+            }
+            Err(e) => self.status = Some(e.to_string()),
+        }
+    }
+
+    // Resets paging and sorting when another resource is selected. The
+    // records of the previous one are dropped while the new ones load, and
+    // so is a record of it still being fetched. Writes still running on it
+    // are only reported.
+    fn select_resource(&mut self, index: usize) {
+        self.sidebar_state.select(Some(index));
+        self.page = 1;
+        self.sort = None;
+        self.column = 0;
+        self.marked.clear();
+        self.retry = None;
+        self.records.clear();
+        self.total = None;
+        self.page_info = None;
+        self.references.clear();
+        self.table_state.select(None);
+        self.detail_request += 1;
+        self.resource_request += 1;
+        self.filter = self
+            .selected_resource()
+            .map(|r| r.filter.clone())
+            .unwrap_or_default();
+        self.load_list();
+    }
+
+    // Resolves the reference fields of the loaded records, with a single
+    // `get_many` call per field for all the ids in the table.
+    pub fn load_references(&mut self) {
+        self.references.clear();
+        let Some(resource) = self.selected_resource() else {
+            return;
+        };
+
+        let fields: Vec<config::Field> = resource
+            .fields
+            .iter()
+            .filter(|f| f.kind == FieldType::Reference && f.reference.is_some())
+            .cloned()
+            .collect();
+
+        let request = self.list_request;
+        for field in fields {
+            let mut ids: Vec<Identifier> = Vec::new();
+            for record in &self.records {
+                let Some(id) = record.fields.get(&field.name) else {
+                    continue;
+                };
+                if let Ok(id) = Identifier::try_from(id) {
+                    if !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+            }
+            if ids.is_empty() {
+                continue;
+            }
+
+            let reference = Resource::new(field.reference.as_deref().unwrap_or_default());
+            let params = GetManyParams { ids, meta: None };
+            self.spawn(move |provider| async move {
+                let result = provider.get_many(reference, params).await;
+                Message::References(request, field, result)
+            });
+        }
+    }
+
+    fn apply_references(&mut self, field: config::Field, result: Result<GetManyResult>) {
+        match result {
+            Ok(result) => {
+                let labels = result
+                    .data
+                    .iter()
+                    .map(|record| {
+                        let label = field
+                            .label
+                            .as_ref()
+                            .and_then(|l| record.fields.get(l))
+                            .map(|v| field.to_input(v))
+                            .unwrap_or_else(|| record.id().to_string());
+                        (record.id().to_string(), label)
+                    })
+                    .collect();
+                self.references.insert(field.name.clone(), labels);
+            }
+            Err(e) => {
+                self.status = Some(format!("Error resolving {}: {}", field.name, e));
+            }
+        }
+    }
+
+    // Fetches the selected row with `get_one` and opens it in the detail pane.
+    pub fn open_detail(&mut self) {
+        let (Some(resource), Some(record)) = (self.selected_resource(), self.selected_record())
+        else {
+            return;
+        };
+
+        let params = GetOneParams {
+            id: record.id().clone(),
+            meta: None,
+        };
+        let relations = resource.relations.clone();
+        let resource = Resource::new(&resource.resource);
+
+        self.detail_request += 1;
+        let request = self.detail_request;
+        self.spawn(move |provider| async move {
+            let result = provider.get_one(resource, params).await;
+            Message::Detail(request, relations, result)
+        });
+    }
+
+    fn apply_detail(&mut self, relations: Vec<config::Relation>, result: Result<GetOneResult>) {
+        match result {
+            Ok(result) => {
+                // Pages of related records asked for the previous record
+                // must not land in this one.
+                self.related_request += 1;
+                self.detail = Some(Detail::new(result.data, &relations));
+                self.status = None;
+            }
+            Err(e) => self.status = Some(format!("Error fetching record: {}", e)),
+        }
+    }
+
+    // Fetches the current page of the related tab shown in the detail pane.
+    pub fn load_related(&mut self) {
+        let Some(resource) = self.selected_resource() else {
+            return;
+        };
+        let resource = Resource::new(&resource.resource);
+        let Some(detail) = &mut self.detail else {
+            return;
+        };
+        let id = detail.record.id().clone();
+        let index = detail.tab;
+        let Some(tab) = detail.current_related() else {
+            return;
+        };
+        let params = tab.params(id);
+
+        self.related_request += 1;
+        let request = self.related_request;
+        self.spawn(move |provider| async move {
+            let result = provider.get_many_reference(resource, params).await;
+            Message::Related(request, index, result)
+        });
+    }
+
+    fn apply_related(&mut self, index: usize, result: Result<GetManyReferenceResult>) {
+        let Some(tab) = self
+            .detail
+            .as_mut()
+            .and_then(|d| d.related.get_mut(index.checked_sub(1)?))
+        else {
+            return;
+        };
+
+        match result {
+            Ok(result) => {
+                tab.set_records(result.data, result.total, result.page_info);
+                self.status = None;
+            }
+            Err(e) => {
+                tab.set_records(Vec::new(), None, None);
+                self.status = Some(format!("Error fetching {}: {}", tab.relation.name, e));
+            }
+        }
+    }
+
+    fn handle_detail_key(&mut self, key: KeyEvent) {
+        let Some(detail) = &mut self.detail else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Backspace => self.detail = None,
+            KeyCode::Char('e') => self.open_edit_form(),
+            KeyCode::Tab | KeyCode::Char(']') => {
+                detail.next_tab();
+                if detail.current_related().is_some_and(|t| !t.loaded) {
+                    self.load_related();
+                }
+            }
+            KeyCode::BackTab | KeyCode::Char('[') => {
+                detail.previous_tab();
+                if detail.current_related().is_some_and(|t| !t.loaded) {
+                    self.load_related();
+                }
+            }
+            _ => match detail.current_related() {
+                None => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => detail.next(),
+                    KeyCode::Up | KeyCode::Char('k') => detail.previous(),
+                    KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Right | KeyCode::Char('l') => {
+                        detail.toggle()
+                    }
+                    KeyCode::Left | KeyCode::Char('h') => detail.collapse(),
+                    _ => {}
+                },
+                Some(tab) => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => tab.next(),
+                    KeyCode::Up | KeyCode::Char('k') => tab.previous(),
+                    KeyCode::Char('n') | KeyCode::PageDown if tab.has_next_page() => {
+                        tab.page += 1;
+                        self.load_related();
+                    }
+                    KeyCode::Char('p') | KeyCode::PageUp if tab.has_previous_page() => {
+                        tab.page -= 1;
+                        self.load_related();
+                    }
+                    KeyCode::Char('s') => {
+                        tab.cycle_sort();
+                        self.load_related();
+                    }
+                    KeyCode::Char('o') => {
+                        tab.toggle_order();
+                        self.load_related();
+                    }
+                    KeyCode::Char('r') => self.load_related(),
+                    _ => {}
+                },
+            },
+        }
+    }
+
+    // Opens an empty form for the selected resource.
+    pub fn open_create_form(&mut self) {
+        if let Some(resource) = self.selected_resource() {
+            self.form = Some(Form::create(resource));
+        }
+    }
+
+    // Opens a form pre-filled from the record shown in the detail pane, or
+    // from the selected row when no detail is open. With marked rows the form
+    // updates all of them instead.
+    pub fn open_edit_form(&mut self) {
+        if self.detail.is_none() && !self.marked.is_empty() {
+            if let Some(resource) = self.selected_resource() {
+                self.form = Some(Form::bulk(resource, self.marked.clone()));
+            }
+            return;
+        }
+
+        let record = match &self.detail {
+            Some(detail) => Some(&detail.record),
+            None => self.selected_record(),
+        };
+
+        if let (Some(resource), Some(record)) = (self.selected_resource(), record) {
+            self.form = Some(Form::edit(resource, record.clone()));
+        }
+    }
+
+    // Sends the form through `create` or `update` and refreshes the views.
+    // The form stays open, frozen, until the answer comes back.
+    pub fn submit_form(&mut self) {
+        let Some(resource) = self.selected_resource() else {
+            return;
+        };
+        let resource = Resource::new(&resource.resource);
+        let (request, name) = (self.resource_request, resource.resource.clone());
+        let Some(form) = &mut self.form else {
+            return;
+        };
+        if form.submitting {
+            return;
+        }
+        let data = match form.data() {
+            Ok(data) => data,
+            Err(e) => {
+                self.status = Some(e.to_string());
+                return;
+            }
+        };
+        // Empty inputs are left untouched by a bulk edit.
+        if matches!(form.mode, FormMode::Bulk(_)) && data.is_empty() {
+            self.status = Some("Nothing to update, fill in a field first".to_string());
+            return;
+        }
+
+        form.submitting = true;
+        match &form.mode {
+            FormMode::Bulk(ids) => {
+                let action = BulkAction::Update(ids.clone(), data);
+                self.form = None;
+                self.run_bulk(action);
+            }
+            FormMode::Create => self.spawn(move |provider| async move {
+                let result = provider
+                    .create(resource, CreateParams { data, meta: None })
+                    .await;
+                Message::Saved(request, name, result.map(|r| ("Created", r.data)))
+            }),
+            FormMode::Edit(record) => {
+                let params = UpdateParams {
+                    id: record.id().clone(),
+                    data,
+                    previous_data: record.clone(),
+                    meta: None,
+                };
+                self.spawn(move |provider| async move {
+                    let result = provider.update(resource, params).await;
+                    Message::Saved(request, name, result.map(|r| ("Updated", r.data)))
+                });
+            }
+        }
+    }
+
+    // Closes the form once saved, or shows what the API rejected in it.
+    fn apply_saved(&mut self, result: Result<(&'static str, Record)>) {
+        match result {
+            Ok((action, record)) => {
+                self.form = None;
+                let id = record.id().to_string();
+                if let Some(detail) = &mut self.detail {
+                    detail.set_record(record);
+                }
+                self.load_list();
+                self.status = Some(format!("{} record {}", action, id));
+            }
+            Err(e) => {
+                if let Some(form) = &mut self.form {
+                    form.submitting = false;
+                    form.show_errors(&e);
+                }
+                self.status = Some(format!("Error saving record: {}", e));
+            }
+        }
+    }
+
+    // Toggles the mark of the selected row.
+    pub fn toggle_mark(&mut self) {
+        let Some(id) = self.selected_record().map(|r| r.id().clone()) else {
+            return;
+        };
+        match self.marked.iter().position(|m| *m == id) {
+            Some(i) => {
+                self.marked.remove(i);
+            }
+            None => self.marked.push(id),
+        }
+    }
+
+    // Asks to delete the marked rows, or the selected one when none is marked.
+    pub fn ask_delete(&mut self) {
+        let ids = if self.marked.is_empty() {
+            self.selected_record()
+                .map(|r| vec![r.id().clone()])
+                .unwrap_or_default()
+        } else {
+            self.marked.clone()
+        };
+
+        if !ids.is_empty() {
+            self.confirm = Some(BulkAction::Delete(ids));
+        }
+    }
+
+    // Runs a bulk action and reports how many records it affected. Failed ids
+    // stay marked and can be retried.
+    pub fn run_bulk(&mut self, action: BulkAction) {
+        let Some(resource) = self.selected_resource() else {
+            return;
+        };
+        let resource = Resource::new(&resource.resource);
+        let (request, name) = (self.resource_request, resource.resource.clone());
+
+        self.spawn(move |provider| async move {
+            let (verb, result) = match &action {
+                BulkAction::Delete(ids) => {
+                    let params = DeleteManyParams {
+                        ids: ids.clone(),
+                        meta: None,
+                    };
+                    let result = provider.delete_many(resource, params).await;
+                    ("deleted", result.map(|r| r.data))
+                }
+                BulkAction::Update(ids, data) => {
+                    let params = UpdateManyParams {
+                        ids: ids.clone(),
+                        data: data.clone(),
+                        meta: None,
+                    };
+                    let result = provider.update_many(resource, params).await;
+                    ("updated", result.map(|r| r.data))
+                }
+            };
+            Message::Bulk(request, name, action, verb, result)
+        });
+    }
+
+    fn apply_bulk(
+        &mut self,
+        action: BulkAction,
+        verb: &'static str,
+        result: Result<Vec<ItemOutcome>>,
+    ) {
+        match result {
+            Ok(outcomes) => {
+                let failed = failed_ids(&outcomes);
+                self.load_list();
+                let mut status = summarize(&outcomes, verb);
+                if failed.is_empty() {
+                    self.retry = None;
+                } else {
+                    status.push_str(" (R to retry)");
+                    self.retry = Some(action.with_ids(failed.clone()));
+                }
+                self.marked = failed;
+                self.status = Some(status);
+            }
+            Err(e) => self.status = Some(format!("Error running bulk action: {}", e)),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if let Some(action) = self.confirm.take() {
+            if key.code == KeyCode::Char('y') {
+                self.run_bulk(action);
+            }
+            return;
+        }
+
+        if self.picker.is_some() {
+            self.handle_picker_key(key);
+            return;
+        }
+
+        if let Some(form) = &mut self.form {
+            match form.handle_key(key) {
+                FormAction::Cancel => self.form = None,
+                FormAction::Submit => self.submit_form(),
+                FormAction::None => {}
+            }
+            return;
+        }
+
+        if self.filter_editor.is_some() {
+            self.handle_filter_key(key);
+            return;
+        }
+
+        if self.detail.is_some() {
+            self.handle_detail_key(key);
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Tab => self.toggle_focus(),
+            KeyCode::Left | KeyCode::Char('h') => self.focus = Focus::Sidebar,
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter
+                if self.focus == Focus::Sidebar =>
+            {
+                self.focus = Focus::Table
+            }
+            KeyCode::Enter if self.focus == Focus::Table => self.open_detail(),
+            KeyCode::Char('c') => self.open_create_form(),
+            KeyCode::Char('C') => self.open_picker(),
+            KeyCode::Char('f') | KeyCode::Char('/') => {
+                self.filter_editor = self.selected_resource().map(FilterEditor::new)
+            }
+            KeyCode::Char('x') => self.remove_last_filter(),
+            KeyCode::Char('X') => self.reset_filter(),
+            KeyCode::Char('e') if self.focus == Focus::Table => self.open_edit_form(),
+            KeyCode::Char(' ') if self.focus == Focus::Table => self.toggle_mark(),
+            KeyCode::Char('u') if self.focus == Focus::Table => self.marked.clear(),
+            KeyCode::Char('d') if self.focus == Focus::Table => self.ask_delete(),
+            KeyCode::Char('R') => {
+                if let Some(action) = self.retry.take() {
+                    self.run_bulk(action);
+                }
+            }
+            KeyCode::Char('r') => self.load_list(),
+            KeyCode::Char('[') if self.focus == Focus::Table => {
+                self.column = self.column.saturating_sub(1)
+            }
+            KeyCode::Char(']') if self.focus == Focus::Table => {
+                let columns = self.selected_resource().map_or(0, |r| r.fields.len());
+                self.column = (self.column + 1).min(columns);
+            }
+            KeyCode::Char('s') if self.focus == Focus::Table => self.sort_by_column(),
+            KeyCode::Char('n') | KeyCode::PageDown
+                if self.page_info.is_some_and(|p| p.has_next_page) =>
+            {
+                self.page += 1;
+                self.load_list()
+            }
+            KeyCode::Char('p') | KeyCode::PageUp
+                if self.page_info.is_some_and(|p| p.has_previous_page) =>
+            {
+                self.page -= 1;
+                self.load_list()
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.next(),
+            KeyCode::Up | KeyCode::Char('k') => self.previous(),
+            KeyCode::Home | KeyCode::Char('g') if self.focus == Focus::Table => {
+                self.table_state.select_first()
+            }
+            KeyCode::End | KeyCode::Char('G') if self.focus == Focus::Table => {
+                self.table_state.select_last()
+            }
+            _ => {}
+        }
+    }
+
+    fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Sidebar => Focus::Table,
+            Focus::Table => Focus::Sidebar,
+        };
+    }
+
+    fn next(&mut self) {
+        match self.focus {
+            Focus::Sidebar => {
+                let len = self.config.resources.len();
+                if let Some(i) = self.sidebar_state.selected() {
+                    if i + 1 < len {
+                        self.select_resource(i + 1);
+                    }
+                }
+            }
+            Focus::Table => {
+                if let Some(i) = self.table_state.selected() {
+                    if i + 1 < self.records.len() {
+                        self.table_state.select(Some(i + 1));
+                    }
+                }
+            }
+        }
+    }
+
+    fn previous(&mut self) {
+        match self.focus {
+            Focus::Sidebar => {
+                if let Some(i) = self.sidebar_state.selected() {
+                    if i > 0 {
+                        self.select_resource(i - 1);
+                    }
+                }
+            }
+            Focus::Table => {
+                if let Some(i) = self.table_state.selected() {
+                    self.table_state.select(Some(i.saturating_sub(1)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::prelude::*;
+
+use ratatui::widgets::{ListState, TableState};
+use std::collections::HashSet;
+
+use crate::config;
+use crate::provider::*;
+
+// One visible line of the record tree.
+pub struct TreeLine {
+    pub depth: usize,
+    pub key: String,
+    pub path: String,
+    pub summary: String,
+    pub expandable: bool,
+    pub expanded: bool,
+}
+
+// RelatedTab holds one page of the records of a configured relation,
+// fetched with `get_many_reference`.
+pub struct RelatedTab {
+    pub relation: config::Relation,
+    pub records: Vec<Record>,
+    pub total: Option<usize>,
+    pub page_info: Option<PageInfo>,
+    pub page: usize,
+    pub sort: SortPayload,
+    pub state: TableState,
+    pub loaded: bool,
+}
+
+impl RelatedTab {
+    pub fn new(relation: config::Relation) -> Self {
+        let sort = SortPayload {
+            field: relation.sort.clone().unwrap_or_else(|| "id".to_string()),
+            order: relation.order,
+        };
+
+        Self {
+            relation,
+            records: Vec::new(),
+            total: None,
+            page_info: None,
+            page: 1,
+            sort,
+            state: TableState::default(),
+            loaded: false,
+        }
+    }
+
+    pub fn params(&self, id: Identifier) -> GetManyReferenceParams {
+        GetManyReferenceParams {
+            target: self.relation.target.clone(),
+            id,
+            pagination: PaginationPayload {
+                page: self.page,
+                per_page: self.relation.per_page,
+            },
+            sort: self.sort.clone(),
+            filter: self.relation.filter.clone(),
+            meta: None,
+        }
+    }
+
+    pub fn set_records(
+        &mut self,
+        records: Vec<Record>,
+        total: Option<usize>,
+        page_info: Option<PageInfo>,
+    ) {
+        self.records = records;
+        self.total = total;
+        self.page_info = page_info;
+        self.loaded = true;
+        self.state.select(if self.records.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    pub fn has_next_page(&self) -> bool {
+        match (self.page_info, self.total) {
+            (Some(info), _) => info.has_next_page,
+            (None, Some(total)) => self.page * self.relation.per_page < total,
+            (None, None) => self.records.len() == self.relation.per_page,
+        }
+    }
+
+    pub fn has_previous_page(&self) -> bool {
+        match self.page_info {
+            Some(info) => info.has_previous_page,
+            None => self.page > 1,
+        }
+    }
+
+    // Sorts by the next configured column, starting over from the first page.
+    pub fn cycle_sort(&mut self) {
+        let fields: Vec<&str> = std::iter::once("id")
+            .chain(self.relation.fields.iter().map(|f| f.name.as_str()))
+            .collect();
+        let next = fields
+            .iter()
+            .position(|f| *f == self.sort.field)
+            .map(|i| (i + 1) % fields.len())
+            .unwrap_or_default();
+        self.sort.field = fields[next].to_string();
+        self.page = 1;
+    }
+
+    pub fn toggle_order(&mut self) {
+        self.sort.order = self.sort.order.toggle();
+        self.page = 1;
+    }
+
+    pub fn next(&mut self) {
+        if let Some(i) = self.state.selected() {
+            if i + 1 < self.records.len() {
+                self.state.select(Some(i + 1));
+            }
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+}
+
+// Detail holds a single record fetched with `get_one`, the state of its
+// tree view and one tab per configured relation.
+pub struct Detail {
+    pub record: Record,
+    pub expanded: HashSet<String>,
+    pub state: ListState,
+    // 0 is the record itself, related tabs follow.
+    pub tab: usize,
+    pub related: Vec<RelatedTab>,
+}
+
+impl Detail {
+    pub fn new(record: Record, relations: &[config::Relation]) -> Self {
+        let mut state = ListState::default();
+        state.select(Some(0));
+
+        Self {
+            record,
+            expanded: HashSet::new(),
+            state,
+            tab: 0,
+            related: relations.iter().cloned().map(RelatedTab::new).collect(),
+        }
+    }
+
+    // Replaces the record, e.g. once saved, keeping the selection within
+    // its lines.
+    pub fn set_record(&mut self, record: Record) {
+        self.record = record;
+        let last = self.lines().len().saturating_sub(1);
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.min(last)));
+        }
+    }
+
+    pub fn next_tab(&mut self) {
+        self.tab = (self.tab + 1) % (self.related.len() + 1);
+    }
+
+    pub fn previous_tab(&mut self) {
+        let tabs = self.related.len() + 1;
+        self.tab = (self.tab + tabs - 1) % tabs;
+    }
+
+    pub fn current_related(&mut self) -> Option<&mut RelatedTab> {
+        self.tab
+            .checked_sub(1)
+            .and_then(|i| self.related.get_mut(i))
+    }
+
+    // Flattens the record into the lines currently visible, honouring which
+    // objects and arrays are expanded.
+    pub fn lines(&self) -> Vec<TreeLine> {
+        let mut lines = vec![TreeLine {
+            depth: 0,
+            key: "id".to_string(),
+            path: "id".to_string(),
+            summary: self.record.id().to_string(),
+            expandable: false,
+            expanded: false,
+        }];
+
+        let mut keys: Vec<&String> = self.record.fields.keys().collect();
+        keys.sort();
+        for key in keys {
+            self.push_value(&mut lines, 0, key, key.clone(), &self.record.fields[key]);
+        }
+
+        lines
+    }
+
+    fn push_value(
+        &self,
+        lines: &mut Vec<TreeLine>,
+        depth: usize,
+        key: &str,
+        path: String,
+        value: &serde_json::Value,
+    ) {
+        let expanded = self.expanded.contains(&path);
+        let (expandable, summary) = match value {
+            serde_json::Value::Object(map) => (true, format!("{{{} keys}}", map.len())),
+            serde_json::Value::Array(items) => (true, format!("[{} items]", items.len())),
+            serde_json::Value::String(s) => (false, s.clone()),
+            other => (false, other.to_string()),
+        };
+
+        lines.push(TreeLine {
+            depth,
+            key: key.to_string(),
+            path: path.clone(),
+            summary,
+            expandable,
+            expanded,
+        });
+
+        if !expanded {
+            return;
+        }
+
+        match value {
+            serde_json::Value::Object(map) => {
+                for (k, v) in map {
+                    self.push_value(lines, depth + 1, k, format!("{}.{}", path, k), v);
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    let k = i.to_string();
+                    self.push_value(lines, depth + 1, &k, format!("{}.{}", path, k), v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn next(&mut self) {
+        let len = self.lines().len();
+        if let Some(i) = self.state.selected() {
+            if i + 1 < len {
+                self.state.select(Some(i + 1));
+            }
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    // Expands the selected node, or collapses it when already expanded.
+    pub fn toggle(&mut self) {
+        let lines = self.lines();
+        let Some(line) = self.state.selected().and_then(|i| lines.get(i)) else {
+            return;
+        };
+
+        if !line.expandable {
+            return;
+        }
+
+        if !self.expanded.remove(&line.path) {
+            self.expanded.insert(line.path.clone());
+        }
+    }
+
+    // Collapses the selected node, or jumps to its parent when it is a leaf.
+    pub fn collapse(&mut self) {
+        let lines = self.lines();
+        let Some((selected, line)) = self
+            .state
+            .selected()
+            .and_then(|i| lines.get(i).map(|line| (i, line)))
+        else {
+            return;
+        };
+
+        if line.expanded {
+            self.expanded.remove(&line.path);
+            return;
+        }
+
+        if line.depth > 0 {
+            let parent = lines[..selected].iter().rposition(|l| l.depth < line.depth);
+            self.state.select(parent);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::prelude::*;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use serde_json::Value;
+
+use crate::config::{self, FieldType};
+use crate::provider::*;
+
+// Operators offered by the editor, in the order they cycle.
+const OPERATORS: [&str; 9] = ["=", "!=", ">", ">=", "<", "<=", "range", "contains", "in"];
+
+// Pseudo field standing for the full-text search.
+const SEARCH: &str = "q";
+
+// What the editor wants the app to do after handling a key.
+pub enum FilterAction {
+    None,
+    Cancel,
+    Add(FilterPayload),
+}
+
+// FilterEditor builds one condition at a time: a field, an operator and the
+// typed operand.
+pub struct FilterEditor {
+    pub fields: Vec<config::Field>,
+    pub field: usize,
+    pub operator: usize,
+    pub value: String,
+}
+
+impl FilterEditor {
+    pub fn new(resource: &config::Resource) -> Self {
+        let search = config::Field {
+            name: SEARCH.to_string(),
+            ..Default::default()
+        };
+        let id = config::Field {
+            name: "id".to_string(),
+            kind: FieldType::Reference,
+            ..Default::default()
+        };
+        let fields = [search, id]
+            .into_iter()
+            .chain(resource.fields.iter().cloned())
+            .collect();
+
+        Self {
+            fields,
+            field: 0,
+            operator: 0,
+            value: String::new(),
+        }
+    }
+
+    pub fn field(&self) -> &config::Field {
+        &self.fields[self.field]
+    }
+
+    pub fn operator(&self) -> &str {
+        if self.is_search() {
+            return "search";
+        }
+        OPERATORS[self.operator]
+    }
+
+    fn is_search(&self) -> bool {
+        self.field == 0
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Result<FilterAction> {
+        match key.code {
+            KeyCode::Esc => return Ok(FilterAction::Cancel),
+            KeyCode::Enter => return self.build().map(FilterAction::Add),
+            KeyCode::Down | KeyCode::Tab => self.field = (self.field + 1) % self.fields.len(),
+            KeyCode::Up | KeyCode::BackTab => {
+                self.field = (self.field + self.fields.len() - 1) % self.fields.len()
+            }
+            KeyCode::Right => self.operator = (self.operator + 1) % OPERATORS.len(),
+            KeyCode::Left => {
+                self.operator = (self.operator + OPERATORS.len() - 1) % OPERATORS.len()
+            }
+            KeyCode::Backspace => {
+                self.value.pop();
+            }
+            KeyCode::Char(c) => self.value.push(c),
+            _ => {}
+        }
+
+        Ok(FilterAction::None)
+    }
+
+    // Turns the editor into a filter, coercing operands with the field type.
+    fn build(&self) -> Result<FilterPayload> {
+        let input = self.value.trim();
+        if self.is_search() {
+            return Ok(FilterPayload::new().search(input));
+        }
+
+        let field = self.field();
+        let filter = match self.operator() {
+            "=" => Filter::Eq(field.coerce(input)?),
+            "!=" => Filter::Ne(field.coerce(input)?),
+            ">" => Filter::Gt(field.coerce(input)?),
+            ">=" => Filter::Gte(field.coerce(input)?),
+            "<" => Filter::Lt(field.coerce(input)?),
+            "<=" => Filter::Lte(field.coerce(input)?),
+            "range" => {
+                let Some((from, to)) = input.split_once("..") else {
+                    return Err(Error::InvalidField {
+                        field: field.name.clone(),
+                        message: "expected a range like 1..10".to_string(),
+                    });
+                };
+                Filter::Range(field.coerce(from)?, field.coerce(to)?)
+            }
+            "contains" => Filter::Contains(input.to_string()),
+            _ => Filter::In(
+                input
+                    .split(',')
+                    .map(|v| field.coerce(v))
+                    .collect::<Result<Vec<Value>>>()?,
+            ),
+        };
+
+        Ok(FilterPayload::new().with(&field.name, filter))
+    }
+
+    pub fn hint(&self) -> &str {
+        match self.operator() {
+            "search" => "text to search",
+            "range" => "from..to",
+            "in" => "comma separated values",
+            _ => "value",
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::prelude::*;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use std::collections::HashMap;
+
+use crate::config::{self, FieldType};
+use crate::provider::*;
+
+// Whether the form creates a new record or edits an existing one.
+pub enum FormMode {
+    Create,
+    Edit(Record),
+    // Updates several records, leaving out the inputs left empty.
+    Bulk(Vec<Identifier>),
+}
+
+// One input of the form, bound to a configured field.
+pub struct FormField {
+    pub field: config::Field,
+    pub value: String,
+}
+
+impl FormField {
+    // Bools and enums are picked with the arrow keys instead of typed.
+    fn cycle(&mut self, forward: bool) {
+        let options: Vec<String> = match self.field.kind {
+            FieldType::Bool => vec!["true".to_string(), "false".to_string()],
+            FieldType::Enum => self.field.choices.clone(),
+            _ => return,
+        };
+        if options.is_empty() {
+            return;
+        }
+
+        let next = match options.iter().position(|o| *o == self.value) {
+            Some(i) if forward => (i + 1) % options.len(),
+            Some(i) => (i + options.len() - 1) % options.len(),
+            None => 0,
+        };
+        self.value = options[next].clone();
+    }
+
+    fn is_picker(&self) -> bool {
+        matches!(self.field.kind, FieldType::Bool | FieldType::Enum)
+    }
+}
+
+// What the form wants the app to do after handling a key.
+pub enum FormAction {
+    None,
+    Cancel,
+    Submit,
+}
+
+// Form is generated from the `fields` of a `config::Resource`.
+pub struct Form {
+    pub mode: FormMode,
+    pub fields: Vec<FormField>,
+    pub selected: usize,
+    // Validation errors by field name, from coercion or from the API.
+    pub errors: HashMap<String, String>,
+    // Set while the record is being saved, the inputs are frozen meanwhile.
+    pub submitting: bool,
+}
+
+impl Form {
+    pub fn create(resource: &config::Resource) -> Self {
+        let fields = resource
+            .fields
+            .iter()
+            .map(|f| FormField {
+                field: f.clone(),
+                value: String::new(),
+            })
+            .collect();
+
+        Self {
+            mode: FormMode::Create,
+            fields,
+            selected: 0,
+            errors: HashMap::new(),
+            submitting: false,
+        }
+    }
+
+    pub fn edit(resource: &config::Resource, record: Record) -> Self {
+        let fields = resource
+            .fields
+            .iter()
+            .map(|f| FormField {
+                field: f.clone(),
+                value: record
+                    .fields
+                    .get(&f.name)
+                    .map(|v| f.to_input(v))
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Self {
+            mode: FormMode::Edit(record),
+            fields,
+            selected: 0,
+            errors: HashMap::new(),
+            submitting: false,
+        }
+    }
+
+    pub fn bulk(resource: &config::Resource, ids: Vec<Identifier>) -> Self {
+        let mut form = Self::create(resource);
+        form.mode = FormMode::Bulk(ids);
+        form
+    }
+
+    pub fn title(&self) -> String {
+        let title = match &self.mode {
+            FormMode::Create => " new record ".to_string(),
+            FormMode::Bulk(ids) => format!(" edit {} records ", ids.len()),
+            FormMode::Edit(record) => format!(" edit record {} ", record.id()),
+        };
+        if self.submitting {
+            return format!("{}saving… ", title);
+        }
+        title
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> FormAction {
+        if self.submitting {
+            return match key.code {
+                KeyCode::Esc => FormAction::Cancel,
+                _ => FormAction::None,
+            };
+        }
+
+        let last = self.fields.len().saturating_sub(1);
+        let Some(field) = self.fields.get_mut(self.selected) else {
+            return match key.code {
+                KeyCode::Esc => FormAction::Cancel,
+                _ => FormAction::None,
+            };
+        };
+
+        match key.code {
+            KeyCode::Esc => return FormAction::Cancel,
+            KeyCode::Enter => return FormAction::Submit,
+            KeyCode::Tab | KeyCode::Down if self.selected < last => self.selected += 1,
+            KeyCode::BackTab | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char(' ') if field.is_picker() => field.cycle(true),
+            KeyCode::Left if field.is_picker() => field.cycle(false),
+            KeyCode::Backspace => {
+                field.value.pop();
+            }
+            KeyCode::Char(c) if !field.is_picker() => field.value.push(c),
+            _ => {}
+        }
+
+        FormAction::None
+    }
+
+    // Shows the field errors carried by `error` and selects the first
+    // offending field.
+    pub fn show_errors(&mut self, error: &Error) {
+        self.errors = error.field_errors();
+        if let Some(i) = self
+            .fields
+            .iter()
+            .position(|f| self.errors.contains_key(&f.field.name))
+        {
+            self.selected = i;
+        }
+    }
+
+    // Coerces every input into the payload sent to the provider. On failure
+    // the offending field is selected so the user can fix it.
+    pub fn data(&mut self) -> Result<HashMap<String, serde_json::Value>> {
+        let mut data = HashMap::new();
+        let bulk = matches!(self.mode, FormMode::Bulk(_));
+        for input in self.fields.iter() {
+            if bulk && input.value.is_empty() {
+                continue;
+            }
+            match input.field.coerce(&input.value) {
+                Ok(value) => {
+                    data.insert(input.field.name.clone(), value);
+                }
+                Err(e) => {
+                    self.show_errors(&e);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(data)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::prelude::*;
+
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use std::time::Duration;
+
+use crate::providers::Registry;
+
+mod app;
+mod detail;
+mod filter_bar;
+mod form;
+mod ui;
+
+pub use app::App;
+
+// How long to wait for a terminal event before redrawing, which is also
+// how long answers of the provider may wait to be shown.
+const TICK_RATE: Duration = Duration::from_millis(50);
+
+// Takes over the terminal and runs the UI on the client `name` until the
+// user quits. Provider calls run on the tokio runtime in the background, so
+// it must be called from within one.
+pub fn run(mut registry: Registry, name: &str) -> Result<()> {
+    let provider = registry.provider(name)?;
+    let config = registry.client(name).cloned().unwrap_or_default();
+
+    let mut terminal = ratatui::init();
+    let mut app = App::new(config, provider).with_registry(registry);
+    app.load_list();
+
+    let result = loop {
+        app.receive();
+        if let Err(e) = terminal.draw(|frame| ui::draw(frame, &mut app)) {
+            break Err(Error::IO(e));
+        }
+
+        match event::poll(TICK_RATE) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    app.handle_key(key);
+                }
+                Ok(_) => {}
+                Err(e) => break Err(Error::IO(e)),
+            },
+            Ok(false) => {}
+            Err(e) => break Err(Error::IO(e)),
+        }
+
+        if app.should_quit {
+            break Ok(());
+        }
+    };
+
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,460 @@
+use crate::prelude::*;
+
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Row, Table, Tabs};
+use ratatui::Frame;
+use std::collections::HashMap;
+
+use crate::config::{self, FieldType};
+use crate::provider::*;
+use crate::tui::app::{App, BulkAction, Focus};
+
+const SELECTED_STYLE: Style = Style::new().add_modifier(Modifier::REVERSED);
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [sidebar, content] =
+        Layout::horizontal([Constraint::Length(24), Constraint::Min(0)]).areas(main);
+
+    draw_sidebar(frame, app, sidebar);
+    if app.detail.is_some() {
+        draw_detail(frame, app, content);
+    } else {
+        let [filter_bar, table] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(content);
+        draw_filter_bar(frame, app, filter_bar);
+        draw_table(frame, app, table);
+    }
+    if app.form.is_some() {
+        draw_form(frame, app, content);
+    }
+    if app.picker.is_some() {
+        draw_picker(frame, app, main);
+    }
+    draw_footer(frame, app, footer);
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let border_style = if focused {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new().fg(Color::DarkGray)
+    };
+
+    Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(border_style)
+}
+
+fn draw_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .config
+        .resources
+        .iter()
+        .map(|r| ListItem::new(r.name.as_str()))
+        .collect();
+
+    let title = format!(" {} ", app.config.name);
+    let list = List::new(items)
+        .block(pane_block(&title, app.focus == Focus::Sidebar))
+        .highlight_style(SELECTED_STYLE);
+
+    frame.render_stateful_widget(list, area, &mut app.sidebar_state);
+}
+
+fn draw_filter_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let line = match &app.filter_editor {
+        Some(editor) => Line::from(vec![
+            format!("‹ {} › ", editor.field().name).bold(),
+            format!("‹ {} › ", editor.operator()).bold(),
+            editor.value.as_str().into(),
+            "█".into(),
+            format!("  {}", editor.hint()).dark_gray(),
+        ]),
+        None if app.filter.is_empty() => Line::from("no filters, f to add one").dark_gray(),
+        None => {
+            let chips = app
+                .filter
+                .q
+                .iter()
+                .map(|q| format!("q: {}", q))
+                .chain(app.filter.conditions.iter().map(|c| c.to_string()));
+            let mut spans = Vec::new();
+            for chip in chips {
+                spans.push(format!(" {} ", chip).black().on_cyan());
+                spans.push(" ".into());
+            }
+            Line::from(spans)
+        }
+    };
+
+    let block = pane_block(" filters ", app.filter_editor.is_some());
+    frame.render_widget(Paragraph::new(line).block(block), area);
+}
+
+fn draw_table(frame: &mut Frame, app: &mut App, area: Rect) {
+    let selected = app.sidebar_state.selected();
+    let Some(resource) = selected.and_then(|i| app.config.resources.get(i)) else {
+        let block = pane_block(" no resources ", app.focus == Focus::Table);
+        frame.render_widget(block, area);
+        return;
+    };
+
+    let column = (app.focus == Focus::Table).then_some(app.column);
+    let table = records_table(
+        &resource.fields,
+        &app.records,
+        Some(&app.references),
+        app.sort.as_ref(),
+        column,
+        &app.marked,
+    );
+    let mut title = match app.total {
+        Some(total) => format!(" {} ({}) ", resource.name, total),
+        None => format!(" {} ", resource.name),
+    };
+    if !app.marked.is_empty() {
+        title.push_str(&format!("{} marked ", app.marked.len()));
+    }
+    if app.pending > 0 {
+        title.push_str("loading… ");
+    }
+    let pages = app
+        .total
+        .map(|t| format!(" of {}", t.div_ceil(resource.per_page.max(1)).max(1)))
+        .unwrap_or_default();
+    let page = format!(" page {}{} ", app.page, pages);
+
+    let table = table.block(pane_block(&title, app.focus == Focus::Table).title_bottom(page));
+
+    frame.render_stateful_widget(table, area, &mut app.table_state);
+}
+
+// Builds a table with the id and the given fields as columns. Reference
+// fields are shown with their resolved labels when available.
+fn records_table<'a>(
+    columns: &'a [config::Field],
+    records: &'a [Record],
+    references: Option<&'a HashMap<String, HashMap<String, String>>>,
+    sort: Option<&SortPayload>,
+    column: Option<usize>,
+    marked: &[Identifier],
+) -> Table<'a> {
+    let header = Row::new(
+        std::iter::once("id")
+            .chain(columns.iter().map(|f| f.name.as_str()))
+            .enumerate()
+            .map(|(i, name)| {
+                let arrow = match sort {
+                    Some(sort) if sort.field == name => match sort.order {
+                        SortOrder::Asc => " ▲",
+                        SortOrder::Desc => " ▼",
+                    },
+                    _ => "",
+                };
+                let cell = format!("{}{}", name, arrow).bold();
+                if column == Some(i) {
+                    cell.underlined()
+                } else {
+                    cell
+                }
+            }),
+    );
+
+    let rows: Vec<Row> = records
+        .iter()
+        .map(|record| {
+            let is_marked = marked.contains(record.id());
+            let id = if is_marked {
+                format!("* {}", record.id())
+            } else {
+                record.id().to_string()
+            };
+            let cells = std::iter::once(id).chain(columns.iter().map(|f| {
+                let Some(value) = record.fields.get(&f.name) else {
+                    return String::new();
+                };
+                let cell = format_cell(f, value);
+                references
+                    .and_then(|r| r.get(&f.name))
+                    .and_then(|labels| labels.get(&cell))
+                    .cloned()
+                    .unwrap_or(cell)
+            }));
+            let row = Row::new(cells);
+            if is_marked {
+                row.yellow()
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let widths = std::iter::once(Constraint::Length(8))
+        .chain(columns.iter().map(|f| match f.kind {
+            FieldType::Bool => Constraint::Length(f.name.len().max(3) as u16),
+            FieldType::Number | FieldType::Date | FieldType::Reference => {
+                Constraint::Length(f.name.len().max(12) as u16)
+            }
+            _ => Constraint::Fill(1),
+        }))
+        .collect::<Vec<_>>();
+
+    Table::new(rows, widths)
+        .header(header)
+        .row_highlight_style(SELECTED_STYLE)
+}
+
+fn draw_detail(frame: &mut Frame, app: &mut App, area: Rect) {
+    let Some(detail) = &mut app.detail else {
+        return;
+    };
+
+    let title = format!(" record {} ", detail.record.id());
+    let block = pane_block(&title, true);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let area = if detail.related.is_empty() {
+        inner
+    } else {
+        let [tabs_area, area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+        let titles = std::iter::once("Fields")
+            .chain(detail.related.iter().map(|t| t.relation.name.as_str()));
+        let tabs = Tabs::new(titles)
+            .select(detail.tab)
+            .highlight_style(SELECTED_STYLE)
+            .block(Block::default().borders(Borders::BOTTOM));
+        frame.render_widget(tabs, tabs_area);
+        area
+    };
+
+    if let Some(tab) = detail.current_related() {
+        let page = format!(
+            " page {}{} | sort {} {} ",
+            tab.page,
+            tab.total
+                .map(|t| format!(" of {}", t.div_ceil(tab.relation.per_page.max(1))))
+                .unwrap_or_default(),
+            tab.sort.field,
+            tab.sort.order,
+        );
+        let table = records_table(
+            &tab.relation.fields,
+            &tab.records,
+            None,
+            Some(&tab.sort),
+            None,
+            &[],
+        )
+        .block(Block::default().title_bottom(page));
+        frame.render_stateful_widget(table, area, &mut tab.state);
+        return;
+    }
+
+    let items: Vec<ListItem> = detail
+        .lines()
+        .into_iter()
+        .map(|line| {
+            let marker = match (line.expandable, line.expanded) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                _ => "  ",
+            };
+            let indent = "  ".repeat(line.depth);
+            Line::from(vec![
+                format!("{}{}", indent, marker).into(),
+                line.key.bold(),
+                ": ".into(),
+                line.summary.into(),
+            ])
+            .into()
+        })
+        .collect();
+
+    let list = List::new(items).highlight_style(SELECTED_STYLE);
+
+    frame.render_stateful_widget(list, area, &mut detail.state);
+}
+
+// Lists the clients of the registry, the current one marked.
+fn draw_picker(frame: &mut Frame, app: &mut App, area: Rect) {
+    let names = app.registry.names();
+    let items: Vec<ListItem> = names
+        .iter()
+        .map(|name| {
+            let marker = if *name == app.config.name {
+                "● "
+            } else {
+                "  "
+            };
+            ListItem::new(format!("{}{}", marker, name))
+        })
+        .collect();
+
+    let height = names.len() as u16 + 2;
+    let width = names.iter().map(|n| n.len()).max().unwrap_or_default() as u16 + 6;
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Length(width.max(20))])
+        .flex(Flex::Center)
+        .areas(area);
+
+    let list = List::new(items)
+        .block(pane_block(" clients ", true))
+        .highlight_style(SELECTED_STYLE);
+    let Some(state) = &mut app.picker else {
+        return;
+    };
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, state);
+}
+
+fn draw_form(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(form) = &app.form else {
+        return;
+    };
+
+    let height = form.fields.len() as u16 + 2;
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    let label_width = form
+        .fields
+        .iter()
+        .map(|f| f.field.name.len())
+        .max()
+        .unwrap_or_default();
+
+    let lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let field = &input.field;
+            let selected = i == form.selected;
+            let label = format!("{:>width$}: ", field.name, width = label_width);
+            let error = form.errors.get(&field.name);
+            let label = match (selected, error) {
+                (_, Some(_)) => label.red().bold(),
+                (true, None) => label.bold(),
+                (false, None) => label.into(),
+            };
+            let mut spans = vec![label];
+
+            match field.kind {
+                FieldType::Bool | FieldType::Enum => {
+                    spans.push(format!("‹ {} ›", input.value).into());
+                }
+                _ => {
+                    spans.push(input.value.as_str().into());
+                    if selected {
+                        spans.push("█".into());
+                    }
+                }
+            }
+
+            if let Some(error) = error {
+                spans.push(format!("  {}", error).red());
+            } else if selected {
+                if let Some(hint) = input_hint(field) {
+                    spans.push(format!("  {}", hint).dark_gray());
+                }
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(pane_block(&form.title(), true)),
+        area,
+    );
+}
+
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    if let Some(action) = &app.confirm {
+        let verb = match action {
+            BulkAction::Delete(_) => "Delete",
+            BulkAction::Update(..) => "Update",
+        };
+        let prompt = format!("{} {} record(s)? y/n", verb, action.ids().len());
+        frame.render_widget(Paragraph::new(Line::from(prompt).red().bold()), area);
+        return;
+    }
+
+    let line = match &app.status {
+        Some(status) => Line::from(status.as_str()).yellow(),
+        None if app.picker.is_some() => {
+            Line::from("esc cancel | j/k move | enter open").dark_gray()
+        }
+        None if app.form.is_some() => {
+            Line::from("esc cancel | tab next field | enter save").dark_gray()
+        }
+        None if app.filter_editor.is_some() => Line::from(
+            "esc cancel | up/down field | left/right operator | enter add",
+        )
+        .dark_gray(),
+        None => match &app.detail {
+            Some(detail) if detail.tab > 0 => {
+                Line::from("esc back | tab next tab | j/k move | n/p page | s sort | o order")
+                    .dark_gray()
+            }
+            Some(_) => Line::from(
+                "esc back | tab next tab | j/k move | enter expand | h collapse | e edit",
+            )
+            .dark_gray(),
+            None => Line::from(
+                "q quit | tab pane | j/k move | n/p page | [/] column | s sort | f filter | x/X drop/reset filters | enter open | c create | e edit | space mark | d delete | C clients",
+            )
+            .dark_gray(),
+        },
+    };
+
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+// Describes what a field accepts, shown next to the selected input.
+fn input_hint(field: &config::Field) -> Option<String> {
+    match field.kind {
+        FieldType::String => None,
+        FieldType::Number => Some(match (field.min, field.max) {
+            (Some(min), Some(max)) => format!("number {}..{}", min, max),
+            (Some(min), None) => format!("number >= {}", min),
+            (None, Some(max)) => format!("number <= {}", max),
+            (None, None) => "number".to_string(),
+        }),
+        FieldType::Bool | FieldType::Enum => Some("←/→ to change".to_string()),
+        FieldType::Date => Some(field.date_format().to_string()),
+        FieldType::Reference => field.reference.as_ref().map(|r| format!("id of {}", r)),
+        FieldType::Json => Some("json".to_string()),
+    }
+}
+
+// Renders a value as a table cell according to the configured field type.
+pub fn format_cell(field: &config::Field, value: &serde_json::Value) -> String {
+    match (field.kind, value) {
+        (FieldType::Bool, serde_json::Value::Bool(true)) => "✓".to_string(),
+        (FieldType::Bool, serde_json::Value::Bool(false)) => "✗".to_string(),
+        _ => value_to_cell(value),
+    }
+}
+
+// Renders a JSON value as a single table cell, without quoting strings.
+pub fn value_to_cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.replace('\n', " "),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,15 @@
+use crate::prelude::*;
+
+// Follows a dotted path such as `data.items` or `meta.0.total` into a JSON
+// value. An empty path is the value itself.
+pub fn lookup<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |value, segment| match value {
+            serde_json::Value::Object(map) => map.get(segment),
+            serde_json::Value::Array(items) => {
+                segment.parse::<usize>().ok().and_then(|i| items.get(i))
+            }
+            _ => None,
+        })
+}
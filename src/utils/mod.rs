@@ -1,3 +1,4 @@
 use crate::prelude::*;
 
 mod direntry_froms;
+pub mod json_path;
@@ -0,0 +1,44 @@
+// Helpers shared by the provider tests, each test crate using a part of them.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use wiremock::{MockServer, Request};
+
+use rtui2::provider::{Record, Resource};
+
+pub fn posts() -> Resource {
+    Resource::new("/posts")
+}
+
+pub fn post(id: usize, title: &str) -> Value {
+    json!({"id": id, "title": title, "userId": 1})
+}
+
+pub fn record(id: usize, title: &str) -> Record {
+    serde_json::from_value(post(id, title)).unwrap()
+}
+
+pub fn data(value: Value) -> HashMap<String, Value> {
+    serde_json::from_value(value).unwrap()
+}
+
+// Query pairs of the only request the server received, in order.
+pub async fn query(server: &MockServer) -> Vec<(String, String)> {
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1, "expected a single request");
+    pairs(&requests[0])
+}
+
+pub fn pairs(request: &Request) -> Vec<(String, String)> {
+    request
+        .url
+        .query_pairs()
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect()
+}
+
+pub fn pair(key: &str, value: &str) -> (String, String) {
+    (key.to_string(), value.to_string())
+}
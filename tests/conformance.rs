@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use rtui2::config::Config;
+use rtui2::provider::conformance::Suite;
+use rtui2::provider::{DataProvider, Identifier, Record};
+use rtui2::providers::{Failures, InMemoryProvider, JsonFileProvider, SqliteProvider};
+use tempfile::TempDir;
+
+fn suite() -> Suite {
+    Suite::new("posts", "comments", "postId")
+}
+
+// Empty collections of the resources the kit exercises.
+fn collections() -> HashMap<String, Vec<Record>> {
+    HashMap::from([
+        ("posts".to_string(), Vec::new()),
+        ("comments".to_string(), Vec::new()),
+    ])
+}
+
+// Client config over `url`, typing the fields the kit writes.
+fn config(dir: &TempDir, provider: &str, url: &Path) -> Config {
+    let yaml = format!(
+        r#"
+name: conformance
+provider: {}
+url: "{}"
+resources:
+  - name: Posts
+    resource: /posts
+    fields:
+      - name: title
+      - name: views
+        type: number
+      - name: published
+        type: bool
+  - name: Comments
+    resource: /comments
+    fields:
+      - name: body
+      - name: postId
+        type: reference
+        reference: /posts
+"#,
+        provider,
+        url.display()
+    );
+    let path = dir.path().join("config.yaml");
+    fs::write(&path, yaml).unwrap();
+    Config::from_file(path.to_str().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn memory_provider_conforms() {
+    let provider = InMemoryProvider::new(collections());
+
+    let report = suite().run(&provider).await;
+    assert!(report.is_ok(), "{}", report);
+}
+
+// Providers are shared as trait objects and called from spawned tasks.
+#[tokio::test(flavor = "multi_thread")]
+async fn shared_provider_conforms_from_a_spawned_task() {
+    let provider: Arc<dyn DataProvider> = Arc::new(InMemoryProvider::new(collections()));
+
+    let report = tokio::spawn(async move { suite().run(provider.as_ref()).await })
+        .await
+        .unwrap();
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[tokio::test]
+async fn json_file_provider_conforms() {
+    let dir = TempDir::new().unwrap();
+    let db = dir.path().join("db.json");
+    fs::write(&db, r#"{"posts": [], "comments": []}"#).unwrap();
+    let provider = JsonFileProvider::new(&config(&dir, "json_file", &db)).unwrap();
+
+    let report = suite().run(&provider).await;
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[tokio::test]
+async fn sqlite_provider_conforms() {
+    let dir = TempDir::new().unwrap();
+    let db = dir.path().join("blog.db");
+    let connection = rusqlite::Connection::open(&db).unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE posts (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                views INTEGER,
+                published BOOLEAN
+            );
+            CREATE TABLE comments (
+                id INTEGER PRIMARY KEY,
+                body TEXT,
+                postId INTEGER REFERENCES posts(id)
+            );",
+        )
+        .unwrap();
+    let provider = SqliteProvider::new(&config(&dir, "sqlite", &db)).unwrap();
+
+    let report = suite().run(&provider).await;
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[tokio::test]
+async fn violations_are_reported_per_scenario() {
+    let failures = Failures {
+        every: None,
+        ids: vec![Identifier::Num(2)],
+    };
+    let provider = InMemoryProvider::new(collections()).with_failures(failures);
+
+    let report = suite().run(&provider).await;
+    let violated: Vec<&str> = report.violations.iter().map(|v| v.scenario).collect();
+    assert_eq!(
+        violated,
+        ["get_one", "update", "reference", "delete_many"],
+        "{}",
+        report
+    );
+    assert!(report.passed.contains(&"filter"));
+}
@@ -0,0 +1,323 @@
+mod common;
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use rtui2::config::Config;
+use rtui2::error::Error;
+use rtui2::provider::*;
+use rtui2::providers::GraphqlProvider;
+
+use common::posts;
+
+// Provider on the mock server, selecting the configured fields of `posts`,
+// templating the list of `entries` and naming the type of `classes`.
+fn provider(server: &MockServer) -> GraphqlProvider {
+    let config: Config = serde_json::from_value(json!({
+        "name": "graphql",
+        "provider": "graphql",
+        "url": server.uri(),
+        "graphql": {"path": "graphql"},
+        "resources": [
+            {
+                "name": "Posts",
+                "resource": "/posts",
+                "fields": [{"name": "title"}, {"name": "views", "type": "number"}],
+            },
+            {
+                "name": "Entries",
+                "resource": "/entries",
+                "fields": [{"name": "title"}],
+                "graphql": {
+                    "type_name": "Entry",
+                    "list": "query ($page: Int, $filter: EntryFilter) { items: entries(page: $page, filter: $filter) { id } }",
+                },
+            },
+            {
+                "name": "Classes",
+                "resource": "/classes",
+                "fields": [{"name": "title"}],
+                "graphql": {"type_name": "Category"},
+            },
+        ],
+    }))
+    .unwrap();
+    GraphqlProvider::new(&config).unwrap()
+}
+
+// Answers every query with `data`.
+async fn respond(server: &MockServer, data: Value) {
+    Mock::given(method("POST"))
+        .and(path("/graphql"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": data })))
+        .mount(server)
+        .await;
+}
+
+// Bodies of the requests the server received, in order.
+async fn bodies(server: &MockServer) -> Vec<Value> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.body_json().unwrap())
+        .collect()
+}
+
+// Query of the only request the server received.
+async fn query(server: &MockServer) -> String {
+    let bodies = bodies(server).await;
+    assert_eq!(bodies.len(), 1, "expected a single request");
+    bodies[0]["query"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn get_list_generates_the_query_and_reads_the_count() {
+    let server = MockServer::start().await;
+    respond(
+        &server,
+        json!({"items": [{"id": 1, "title": "a"}], "total": {"count": 25}}),
+    )
+    .await;
+
+    let filter = FilterPayload::new()
+        .with("userId", Filter::Eq(json!(1)))
+        .with("status", Filter::Ne(json!("draft")))
+        .with("views", Filter::Gt(json!(10)))
+        .search("hello");
+    let params = GetListParams {
+        pagination: Some(PaginationPayload {
+            page: 2,
+            per_page: 10,
+        }),
+        sort: Some(SortPayload {
+            field: "title".to_string(),
+            order: SortOrder::Desc,
+        }),
+        filter: Some(filter),
+        meta: None,
+    };
+    let result = provider(&server).get_list(posts(), params).await.unwrap();
+
+    let filter = r#"{q: "hello", status_neq: "draft", userId: 1, views_gt: 10}"#;
+    assert_eq!(
+        query(&server).await,
+        format!(
+            "query {{ items: allPosts(filter: {0}, page: 1, perPage: 10, sortField: \"title\", \
+             sortOrder: \"DESC\") {{ id title views }} total: _allPostsMeta(filter: {0}) \
+             {{ count }} }}",
+            filter
+        )
+    );
+    assert_eq!(result.data.len(), 1);
+    assert_eq!(result.total, Some(25));
+}
+
+#[tokio::test]
+async fn filters_without_a_key_are_rejected() {
+    let server = MockServer::start().await;
+
+    let params = GetListParams {
+        pagination: None,
+        sort: None,
+        filter: Some(FilterPayload::new().with("title", Filter::Contains("rust".to_string()))),
+        meta: None,
+    };
+    let Err(error) = provider(&server).get_list(posts(), params).await else {
+        panic!("expected an error");
+    };
+
+    assert!(matches!(error, Error::InvalidField { .. }));
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn get_list_sends_variables_to_a_template() {
+    let server = MockServer::start().await;
+    respond(&server, json!({"items": [{"id": 1}], "total": 1})).await;
+
+    let params = GetListParams {
+        pagination: Some(PaginationPayload {
+            page: 1,
+            per_page: 5,
+        }),
+        sort: None,
+        filter: Some(FilterPayload::new().with("title", Filter::Eq(json!("x")))),
+        meta: None,
+    };
+    let result = provider(&server)
+        .get_list(Resource::new("/entries"), params)
+        .await
+        .unwrap();
+
+    let body = &bodies(&server).await[0];
+    assert!(body["query"].as_str().unwrap().contains("items: entries("));
+
+    assert_eq!(
+        body["variables"],
+        json!({"page": 0, "perPage": 5, "filter": {"title": "x"}})
+    );
+    assert_eq!(result.total, Some(1));
+}
+
+#[tokio::test]
+async fn get_one_queries_the_type() {
+    let server = MockServer::start().await;
+    respond(&server, json!({"item": {"id": 3, "title": "c"}})).await;
+
+    let params = GetOneParams {
+        id: Identifier::Num(3),
+        meta: None,
+    };
+    let result = provider(&server).get_one(posts(), params).await.unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        "query { item: Post(id: 3) { id title views } }"
+    );
+    assert_eq!(result.data.id, Identifier::Num(3));
+}
+
+#[tokio::test]
+async fn get_many_filters_on_ids() {
+    let server = MockServer::start().await;
+    respond(&server, json!({"items": [], "total": {"count": 0}})).await;
+
+    let params = GetManyParams {
+        ids: vec![Identifier::Num(1), Identifier::Str("b".to_string())],
+        meta: None,
+    };
+    provider(&server).get_many(posts(), params).await.unwrap();
+
+    assert!(query(&server)
+        .await
+        .starts_with(r#"query { items: allPosts(filter: {ids: [1, "b"]}) { id title views }"#));
+}
+
+#[tokio::test]
+async fn lists_of_a_configured_type_are_named_after_its_plural() {
+    let server = MockServer::start().await;
+    respond(&server, json!({"items": [], "total": {"count": 0}})).await;
+
+    let params = GetManyParams {
+        ids: vec![Identifier::Num(1)],
+        meta: None,
+    };
+    provider(&server)
+        .get_many(Resource::new("/classes"), params)
+        .await
+        .unwrap();
+
+    assert!(query(&server)
+        .await
+        .starts_with("query { items: allCategories(filter: {ids: [1]}) { id title }"));
+}
+
+#[tokio::test]
+async fn get_many_reference_singularizes_the_foreign_key() {
+    let server = MockServer::start().await;
+    respond(&server, json!({"items": [], "total": {"count": 0}})).await;
+
+    let params = GetManyReferenceParams {
+        target: "orders".to_string(),
+        id: Identifier::Num(4),
+        pagination: PaginationPayload {
+            page: 1,
+            per_page: 5,
+        },
+        sort: SortPayload {
+            field: "id".to_string(),
+            order: SortOrder::Asc,
+        },
+        filter: FilterPayload::new(),
+        meta: None,
+    };
+    provider(&server)
+        .get_many_reference(Resource::new("/addresses"), params)
+        .await
+        .unwrap();
+
+    assert!(query(&server)
+        .await
+        .starts_with("query { items: allOrders(filter: {addressId: 4}, page: 0, perPage: 5, "));
+}
+
+#[tokio::test]
+async fn mutations_are_named_after_the_singular_type() {
+    let server = MockServer::start().await;
+    respond(&server, json!({"item": {"id": 1, "street": "x"}})).await;
+
+    let params = UpdateParams {
+        id: Identifier::Num(1),
+        data: HashMap::from([("street".to_string(), json!("x"))]),
+        previous_data: serde_json::from_value(json!({"id": 1, "street": "y"})).unwrap(),
+        meta: None,
+    };
+    provider(&server)
+        .update(Resource::new("/addresses"), params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        r#"mutation { item: updateAddress(id: 1, street: "x") { id } }"#
+    );
+}
+
+#[tokio::test]
+async fn errors_of_the_response_are_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/graphql"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": null,
+            "errors": [{"message": "Post not found"}],
+        })))
+        .mount(&server)
+        .await;
+
+    let params = GetOneParams {
+        id: Identifier::Num(9),
+        meta: None,
+    };
+    let Err(error) = provider(&server).get_one(posts(), params).await else {
+        panic!("expected an error");
+    };
+
+    assert!(matches!(error, Error::Graphql(_)));
+    assert_eq!(error.to_string(), "Post not found");
+}
+
+#[tokio::test]
+async fn delete_many_runs_a_mutation_per_id() {
+    let server = MockServer::start().await;
+    respond(&server, json!({"item": {"id": 1}})).await;
+
+    let params = DeleteManyParams {
+        ids: vec![Identifier::Num(1), Identifier::Num(2)],
+        meta: None,
+    };
+    let result = provider(&server)
+        .delete_many(posts(), params)
+        .await
+        .unwrap();
+
+    let mut queries: Vec<String> = bodies(&server)
+        .await
+        .iter()
+        .map(|body| body["query"].as_str().unwrap().to_string())
+        .collect();
+    queries.sort();
+    assert_eq!(
+        queries,
+        [
+            "mutation { item: deletePost(id: 1) { id title views } }",
+            "mutation { item: deletePost(id: 2) { id title views } }",
+        ]
+    );
+    assert!(failed_ids(&result.data).is_empty());
+}
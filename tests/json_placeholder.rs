@@ -0,0 +1,535 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Map, Value};
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+use rtui2::config::HttpConfig;
+use rtui2::error::Error;
+use rtui2::provider::*;
+use rtui2::providers::JsonPlaceholder;
+
+use common::*;
+
+async fn provider(server: &MockServer) -> JsonPlaceholder {
+    JsonPlaceholder::new(&format!("{}/", server.uri()), &HttpConfig::default()).unwrap()
+}
+
+fn list_params(filter: Option<FilterPayload>) -> GetListParams {
+    GetListParams {
+        pagination: Some(PaginationPayload {
+            page: 2,
+            per_page: 10,
+        }),
+        sort: Some(SortPayload {
+            field: "title".to_string(),
+            order: SortOrder::Desc,
+        }),
+        filter,
+        meta: None,
+    }
+}
+
+#[tokio::test]
+async fn get_list_sends_pagination_and_sort_and_reads_total() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Total-Count", "25")
+                .set_body_json(json!([post(11, "a"), post(12, "b")])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let result = provider(&server)
+        .await
+        .get_list(posts(), list_params(None))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("_sort", "title"),
+            pair("_order", "desc"),
+            pair("_page", "2"),
+            pair("_limit", "10"),
+        ]
+    );
+    assert_eq!(result.data, vec![record(11, "a"), record(12, "b")]);
+    assert_eq!(result.total, Some(25));
+    let page_info = result.page_info.unwrap();
+    assert!(page_info.has_next_page);
+    assert!(page_info.has_previous_page);
+}
+
+#[tokio::test]
+async fn get_list_without_total_header_counts_records() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([post(1, "a")])))
+        .mount(&server)
+        .await;
+
+    let params = GetListParams {
+        pagination: None,
+        sort: None,
+        filter: None,
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .get_list(posts(), params)
+        .await
+        .unwrap();
+
+    assert!(query(&server).await.is_empty());
+    assert_eq!(result.total, Some(1));
+    assert!(result.page_info.is_none());
+}
+
+#[tokio::test]
+async fn get_list_encodes_filters() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&server)
+        .await;
+
+    let filter = FilterPayload::new()
+        .with("userId", Filter::Eq(json!(1)))
+        .with("status", Filter::Ne(json!("draft & old")))
+        .with("views", Filter::Gt(json!(10)))
+        .with("likes", Filter::Lte(json!(5)))
+        .with(
+            "date",
+            Filter::Range(json!("2024-01-01"), json!("2024-12-31")),
+        )
+        .with("title", Filter::Contains("a.b (c)".to_string()))
+        .with("tag", Filter::In(vec![json!("x"), json!("y")]))
+        .search("hello world");
+    provider(&server)
+        .await
+        .get_list(posts(), list_params(Some(filter)))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("userId", "1"),
+            pair("status_ne", "draft & old"),
+            pair("views_gte", "10"),
+            pair("views_ne", "10"),
+            pair("likes_lte", "5"),
+            pair("date_gte", "2024-01-01"),
+            pair("date_lte", "2024-12-31"),
+            pair("title_like", r"a\.b \(c\)"),
+            pair("tag", "x"),
+            pair("tag", "y"),
+            pair("q", "hello world"),
+            pair("_sort", "title"),
+            pair("_order", "desc"),
+            pair("_page", "2"),
+            pair("_limit", "10"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_one_requests_the_record_url() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts/7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(post(7, "seven")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GetOneParams {
+        id: Identifier::Num(7),
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .get_one(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(result.data, record(7, "seven"));
+}
+
+#[tokio::test]
+async fn get_many_repeats_the_id_parameter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([post(1, "a"), post(3, "c")])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GetManyParams {
+        ids: vec![Identifier::Num(1), Identifier::Num(3)],
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .get_many(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(query(&server).await, vec![pair("id", "1"), pair("id", "3")]);
+    assert_eq!(result.data.len(), 2);
+}
+
+#[tokio::test]
+async fn get_many_reference_nests_the_target_under_the_record() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts/4/comments"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Total-Count", "6")
+                .set_body_json(json!([{"id": 1, "postId": 4, "name": "first"}])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GetManyReferenceParams {
+        target: "comments".to_string(),
+        id: Identifier::Num(4),
+        pagination: PaginationPayload {
+            page: 1,
+            per_page: 5,
+        },
+        sort: SortPayload {
+            field: "name".to_string(),
+            order: SortOrder::Asc,
+        },
+        filter: FilterPayload::new().with("name", Filter::Contains("fir".to_string())),
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .get_many_reference(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("name_like", "fir"),
+            pair("_sort", "name"),
+            pair("_order", "asc"),
+            pair("_page", "1"),
+            pair("_limit", "5"),
+        ]
+    );
+    assert_eq!(result.total, Some(6));
+    assert!(result.page_info.unwrap().has_next_page);
+}
+
+#[tokio::test]
+async fn create_posts_the_data() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/posts"))
+        .and(body_json(json!({"title": "new", "userId": 1})))
+        .respond_with(ResponseTemplate::new(201).set_body_json(post(101, "new")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = CreateParams {
+        data: data(json!({"title": "new", "userId": 1})),
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .create(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(result.data, record(101, "new"));
+}
+
+#[tokio::test]
+async fn update_puts_the_data_to_the_record_url() {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/posts/2"))
+        .and(body_json(json!({"title": "changed", "userId": 1})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(post(2, "changed")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = UpdateParams {
+        id: Identifier::Num(2),
+        data: data(json!({"title": "changed"})),
+        previous_data: record(2, "before"),
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .update(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(result.data, record(2, "changed"));
+}
+
+#[tokio::test]
+async fn update_keeps_the_fields_left_out_of_the_data() {
+    let server = MockServer::start().await;
+    Mock::given(method("PUT"))
+        .and(path("/posts/2"))
+        .and(body_json(
+            json!({"title": "changed", "userId": 1, "body": "kept"}),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(post(2, "changed")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = UpdateParams {
+        id: Identifier::Num(2),
+        data: data(json!({"title": "changed"})),
+        previous_data: serde_json::from_value(
+            json!({"id": 2, "title": "before", "userId": 1, "body": "kept"}),
+        )
+        .unwrap(),
+        meta: None,
+    };
+    provider(&server)
+        .await
+        .update(posts(), params)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn update_many_patches_each_record_url_and_reports_failures() {
+    let server = MockServer::start().await;
+    for id in [1, 2] {
+        Mock::given(method("PATCH"))
+            .and(path(format!("/posts/{}", id)))
+            .and(body_json(json!({"title": "bulk"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(post(id, "bulk")))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("PATCH"))
+        .and(path("/posts/3"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(json!({"message": "locked"})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = UpdateManyParams {
+        ids: vec![Identifier::Num(1), Identifier::Num(2), Identifier::Num(3)],
+        data: data(json!({"title": "bulk"})),
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .update_many(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(failed_ids(&result.data), vec![Identifier::Num(3)]);
+    assert_eq!(
+        summarize(&result.data, "updated"),
+        "2 updated, 1 failed: 403 Forbidden: locked"
+    );
+}
+
+// Answers like json-server on a single stored todo: `PUT` replaces it and
+// `PATCH` merges into it.
+fn json_server_todo(todo: Arc<Mutex<Value>>) -> impl Fn(&Request) -> ResponseTemplate {
+    move |request: &Request| {
+        let mut todo = todo.lock().unwrap();
+        if request.method == Method::PUT || request.method == Method::PATCH {
+            let body: Map<String, Value> = request.body_json().unwrap();
+            if request.method == Method::PUT {
+                *todo = json!({"id": todo["id"]});
+            }
+            todo.as_object_mut().unwrap().extend(body);
+        }
+        ResponseTemplate::new(200).set_body_json(todo.clone())
+    }
+}
+
+#[tokio::test]
+async fn partial_bulk_update_keeps_the_other_fields() {
+    let server = MockServer::start().await;
+    let todo = Arc::new(Mutex::new(
+        json!({"id": 1, "title": "write tests", "userId": 3, "completed": false}),
+    ));
+    Mock::given(path("/todos/1"))
+        .respond_with(json_server_todo(todo.clone()))
+        .mount(&server)
+        .await;
+
+    let params = UpdateManyParams {
+        ids: vec![Identifier::Num(1)],
+        data: data(json!({"completed": true})),
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .update_many(Resource::new("/todos"), params)
+        .await
+        .unwrap();
+
+    assert!(failed_ids(&result.data).is_empty());
+    assert_eq!(
+        *todo.lock().unwrap(),
+        json!({"id": 1, "title": "write tests", "userId": 3, "completed": true})
+    );
+}
+
+#[tokio::test]
+async fn delete_returns_the_previous_data() {
+    let server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/posts/5"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = DeleteParams {
+        id: Identifier::Num(5),
+        previous_data: Some(record(5, "gone")),
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .delete(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(result.data, record(5, "gone"));
+}
+
+#[tokio::test]
+async fn delete_many_deletes_each_record_url() {
+    let server = MockServer::start().await;
+    for id in [4, 5] {
+        Mock::given(method("DELETE"))
+            .and(path(format!("/posts/{}", id)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let params = DeleteManyParams {
+        ids: vec![Identifier::Num(4), Identifier::Num(5)],
+        meta: None,
+    };
+    let result = provider(&server)
+        .await
+        .delete_many(posts(), params)
+        .await
+        .unwrap();
+
+    assert!(result.data.iter().all(ItemOutcome::is_ok));
+}
+
+#[tokio::test]
+async fn non_success_status_becomes_an_http_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts/9"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("not here"))
+        .mount(&server)
+        .await;
+
+    let params = GetOneParams {
+        id: Identifier::Num(9),
+        meta: None,
+    };
+    let Err(error) = provider(&server).await.get_one(posts(), params).await else {
+        panic!("expected an error");
+    };
+
+    let Error::Http(http) = &error else {
+        panic!("expected an HTTP error, got {:?}", error);
+    };
+    assert_eq!(http.status, StatusCode::NOT_FOUND);
+    assert_eq!(http.method, Method::GET);
+    assert!(http.url.ends_with("/posts/9"));
+    assert_eq!(http.message().as_deref(), Some("not here"));
+}
+
+#[tokio::test]
+async fn validation_errors_are_reported_per_field() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/posts"))
+        .respond_with(
+            ResponseTemplate::new(422)
+                .set_body_json(json!({"errors": {"title": ["can't be blank"]}})),
+        )
+        .mount(&server)
+        .await;
+
+    let params = CreateParams {
+        data: data(json!({"title": ""})),
+        meta: None,
+    };
+    let Err(error) = provider(&server).await.create(posts(), params).await else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(error.status(), Some(StatusCode::UNPROCESSABLE_ENTITY));
+    assert_eq!(
+        error.field_errors().get("title").map(String::as_str),
+        Some("can't be blank")
+    );
+}
+
+#[tokio::test]
+async fn malformed_bodies_are_parse_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("[{\"id\": 1,"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/posts/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"title": "no id"})))
+        .mount(&server)
+        .await;
+
+    let api = provider(&server).await;
+    let params = GetListParams {
+        pagination: None,
+        sort: None,
+        filter: None,
+        meta: None,
+    };
+    let list = api.get_list(posts(), params).await;
+    assert!(matches!(list, Err(Error::Parse(_))));
+
+    let params = GetOneParams {
+        id: Identifier::Num(1),
+        meta: None,
+    };
+    let one = api.get_one(posts(), params).await;
+    assert!(matches!(one, Err(Error::Parse(_))));
+}
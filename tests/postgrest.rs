@@ -0,0 +1,313 @@
+mod common;
+
+use serde_json::json;
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use rtui2::config::Config;
+use rtui2::provider::*;
+use rtui2::providers::PostgrestProvider;
+
+use common::*;
+
+// Provider on the mock server, selecting the configured fields of `posts`.
+fn provider(server: &MockServer) -> PostgrestProvider {
+    let config: Config = serde_json::from_value(json!({
+        "name": "postgrest",
+        "provider": "postgrest",
+        "url": server.uri(),
+        "postgrest": {"schema": "blog"},
+        "resources": [{
+            "name": "Posts",
+            "resource": "/posts",
+            "fields": [
+                {"name": "title"},
+                {"name": "body"},
+                {"name": "views", "type": "number"},
+            ],
+        }],
+    }))
+    .unwrap();
+    PostgrestProvider::new(&config).unwrap()
+}
+
+#[tokio::test]
+async fn get_list_encodes_filters_order_and_range() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .and(header("Accept-Profile", "blog"))
+        .and(header("Prefer", "count=exact"))
+        .and(header("Range-Unit", "items"))
+        .and(header("Range", "10-19"))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .insert_header("Content-Range", "10-11/25")
+                .set_body_json(json!([post(11, "a"), post(12, "b")])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let filter = FilterPayload::new()
+        .with("userId", Filter::Eq(json!(1)))
+        .with("status", Filter::Ne(json!("draft")))
+        .with("views", Filter::Gt(json!(10)))
+        .with(
+            "date",
+            Filter::Range(json!("2024-01-01"), json!("2024-12-31")),
+        )
+        .with("title", Filter::Contains("50%_off*".to_string()))
+        .with("tag", Filter::In(vec![json!("x"), json!("a,b")]))
+        .search("a_b");
+    let params = GetListParams {
+        pagination: Some(PaginationPayload {
+            page: 2,
+            per_page: 10,
+        }),
+        sort: Some(SortPayload {
+            field: "title".to_string(),
+            order: SortOrder::Desc,
+        }),
+        filter: Some(filter),
+        meta: None,
+    };
+    let result = provider(&server).get_list(posts(), params).await.unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("userId", "eq.1"),
+            pair("status", "neq.draft"),
+            pair("views", "gt.10"),
+            pair("date", "gte.2024-01-01"),
+            pair("date", "lte.2024-12-31"),
+            pair("title", r"ilike.*50\%\_off_*"),
+            pair("tag", r#"in.(x,"a,b")"#),
+            pair("or", r"(title.ilike.*a\_b*,body.ilike.*a\_b*)"),
+            pair("order", "title.desc"),
+            pair("select", "id,title,body,views"),
+        ]
+    );
+    assert_eq!(result.data, vec![record(11, "a"), record(12, "b")]);
+    assert_eq!(result.total, Some(25));
+    assert!(result.page_info.unwrap().has_next_page);
+}
+
+#[tokio::test]
+async fn get_one_asks_for_a_single_object() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .and(header("Accept", "application/vnd.pgrst.object+json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(post(3, "c")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GetOneParams {
+        id: Identifier::Num(3),
+        meta: None,
+    };
+    let result = provider(&server).get_one(posts(), params).await.unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![pair("id", "eq.3"), pair("select", "id,title,body,views")]
+    );
+    assert_eq!(result.data, record(3, "c"));
+}
+
+#[tokio::test]
+async fn get_many_filters_the_ids_in_a_list() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([post(1, "a")])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GetManyParams {
+        ids: vec![Identifier::Num(1), Identifier::Str("a.b".to_string())],
+        meta: None,
+    };
+    provider(&server).get_many(posts(), params).await.unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("id", r#"in.(1,"a.b")"#),
+            pair("select", "id,title,body,views"),
+        ]
+    );
+}
+
+fn reference_params(target: &str) -> GetManyReferenceParams {
+    GetManyReferenceParams {
+        target: target.to_string(),
+        id: Identifier::Num(4),
+        pagination: PaginationPayload {
+            page: 1,
+            per_page: 5,
+        },
+        sort: SortPayload {
+            field: "id".to_string(),
+            order: SortOrder::Asc,
+        },
+        filter: FilterPayload::new(),
+        meta: None,
+    }
+}
+
+#[tokio::test]
+async fn get_many_reference_guesses_the_foreign_key_column() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/orders"))
+        .and(header("Range", "0-4"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Range", "0-0/1")
+                .set_body_json(json!([{"id": 1, "address_id": 4}])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let result = provider(&server)
+        .get_many_reference(Resource::new("/addresses"), reference_params("orders"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("address_id", "eq.4"),
+            pair("order", "id.asc"),
+            pair("select", "*"),
+        ]
+    );
+    assert_eq!(result.total, Some(1));
+}
+
+#[tokio::test]
+async fn get_many_reference_takes_the_column_of_the_target() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/comments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    provider(&server)
+        .get_many_reference(posts(), reference_params("comments.parent"))
+        .await
+        .unwrap();
+
+    assert_eq!(query(&server).await[0], pair("parent", "eq.4"));
+}
+
+#[tokio::test]
+async fn create_returns_the_representation() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/posts"))
+        .and(header("Content-Profile", "blog"))
+        .and(header("Prefer", "return=representation"))
+        .and(header("Accept", "application/vnd.pgrst.object+json"))
+        .and(body_json(json!({"title": "new"})))
+        .respond_with(ResponseTemplate::new(201).set_body_json(post(7, "new")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = CreateParams {
+        data: data(json!({"title": "new"})),
+        meta: None,
+    };
+    let result = provider(&server).create(posts(), params).await.unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![pair("select", "id,title,body,views")]
+    );
+    assert_eq!(result.data, record(7, "new"));
+}
+
+#[tokio::test]
+async fn update_many_reports_the_rows_left_untouched() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/posts"))
+        .and(body_json(json!({"title": "bulk"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"id": 1}])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = UpdateManyParams {
+        ids: vec![Identifier::Num(1), Identifier::Num(2)],
+        data: data(json!({"title": "bulk"})),
+        meta: None,
+    };
+    let result = provider(&server)
+        .update_many(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![pair("id", "in.(1,2)"), pair("select", "id")]
+    );
+    assert_eq!(failed_ids(&result.data), vec![Identifier::Num(2)]);
+}
+
+#[tokio::test]
+async fn update_many_matches_the_returned_ids_as_text() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"id": 1}, {"id": "2"}])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = UpdateManyParams {
+        ids: vec![Identifier::Str("1".to_string()), Identifier::Num(2)],
+        data: data(json!({"title": "bulk"})),
+        meta: None,
+    };
+    let result = provider(&server)
+        .update_many(posts(), params)
+        .await
+        .unwrap();
+
+    assert!(failed_ids(&result.data).is_empty());
+}
+
+#[tokio::test]
+async fn delete_many_fails_every_id_with_the_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/posts"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(json!({"message": "denied"})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = DeleteManyParams {
+        ids: vec![Identifier::Num(1), Identifier::Num(2)],
+        meta: None,
+    };
+    let result = provider(&server)
+        .delete_many(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        failed_ids(&result.data),
+        vec![Identifier::Num(1), Identifier::Num(2)]
+    );
+}
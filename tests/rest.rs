@@ -0,0 +1,374 @@
+mod common;
+
+use serde_json::{json, Value};
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use rtui2::config::Config;
+use rtui2::error::Error;
+use rtui2::provider::*;
+use rtui2::providers::RestProvider;
+
+use common::*;
+
+// Provider on the mock server with the `rest` settings of the client, and
+// `resources` overriding them per resource.
+fn provider(server: &MockServer, rest: Value, resources: Value) -> RestProvider {
+    let config: Config = serde_json::from_value(json!({
+        "name": "rest",
+        "url": format!("{}/api", server.uri()),
+        "rest": rest,
+        "resources": resources,
+    }))
+    .unwrap();
+    RestProvider::new(&config).unwrap()
+}
+
+// Provider with the json-server conventions.
+fn default_provider(server: &MockServer) -> RestProvider {
+    provider(server, json!({}), json!([]))
+}
+
+// Provider of an API keying `enrollments` by student and course.
+fn composite_provider(server: &MockServer) -> RestProvider {
+    provider(
+        server,
+        json!({}),
+        json!([{
+            "name": "Enrollments",
+            "resource": "/enrollments",
+            "fields": [],
+            "rest": {
+                "endpoints": {
+                    "one": {"path": "students/{id.student}/courses/{id.1}"},
+                    "many": {"path": "enrollments"},
+                },
+                "id_field": ["student", "course"],
+            },
+        }]),
+    )
+}
+
+fn enrollments() -> Resource {
+    Resource::new("/enrollments")
+}
+
+fn enrollment(student: &str, course: usize) -> Value {
+    json!({"student": student, "course": course})
+}
+
+fn list_params(filter: Option<FilterPayload>) -> GetListParams {
+    GetListParams {
+        pagination: Some(PaginationPayload {
+            page: 3,
+            per_page: 10,
+        }),
+        sort: Some(SortPayload {
+            field: "title".to_string(),
+            order: SortOrder::Desc,
+        }),
+        filter,
+        meta: None,
+    }
+}
+
+#[tokio::test]
+async fn get_list_follows_json_server_by_default() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/posts"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Total-Count", "42")
+                .set_body_json(json!([post(21, "a")])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let filter = FilterPayload::new()
+        .with("userId", Filter::Eq(json!(1)))
+        .with("views", Filter::Gt(json!(10)))
+        .with("likes", Filter::Lt(json!(5)))
+        .with("title", Filter::Contains("a.b (c)".to_string()))
+        .with("tag", Filter::In(vec![json!("x"), json!("y")]))
+        .search("hello");
+    let result = default_provider(&server)
+        .get_list(posts(), list_params(Some(filter)))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("userId", "1"),
+            pair("views_gte", "10"),
+            pair("views_ne", "10"),
+            pair("likes_lte", "5"),
+            pair("likes_ne", "5"),
+            pair("title_like", r"a\.b \(c\)"),
+            pair("tag", "x"),
+            pair("tag", "y"),
+            pair("q", "hello"),
+            pair("_sort", "title"),
+            pair("_order", "desc"),
+            pair("_page", "3"),
+            pair("_limit", "10"),
+        ]
+    );
+    assert_eq!(result.total, Some(42));
+}
+
+#[tokio::test]
+async fn get_list_applies_the_configured_conventions() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v2/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": {"items": [post(1, "a"), post(2, "b")]},
+            "meta": {"total": 12},
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let rest = json!({
+        "endpoints": {"list": {"path": "v2/{resource}"}},
+        "pagination": "offset",
+        "offset_param": "skip",
+        "limit_param": "take",
+        "sort_param": "sort",
+        "desc_prefix": "-",
+        "filters": {"gt": "{field}[gt]", "contains": "{field}[like]"},
+        "in_separator": ",",
+        "search_param": "search",
+        "total": {"body": "meta.total"},
+        "records_path": "data.items",
+    });
+    let filter = FilterPayload::new()
+        .with("views", Filter::Gt(json!(10)))
+        .with("title", Filter::Contains("a.b".to_string()))
+        .with("tag", Filter::In(vec![json!("x"), json!("y")]))
+        .search("hello");
+    let result = provider(&server, rest, json!([]))
+        .get_list(posts(), list_params(Some(filter)))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("views[gt]", "10"),
+            pair("title[like]", "a.b"),
+            pair("tag", "x,y"),
+            pair("search", "hello"),
+            pair("sort", "-title"),
+            pair("skip", "20"),
+            pair("take", "10"),
+        ]
+    );
+    assert_eq!(result.data.len(), 2);
+    assert_eq!(result.total, Some(12));
+}
+
+#[tokio::test]
+async fn endpoints_are_overridden_per_resource() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/posts/5/edit"))
+        .and(body_json(json!({"title": "changed"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"data": post(5, "changed")})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let resources = json!([{
+        "name": "Posts",
+        "resource": "/posts",
+        "fields": [],
+        "rest": {
+            "endpoints": {"update": {"method": "post", "path": "{resource}/{id}/edit"}},
+            "record_path": "data",
+        },
+    }]);
+    let params = UpdateParams {
+        id: Identifier::Num(5),
+        data: data(json!({"title": "changed"})),
+        previous_data: serde_json::from_value(post(5, "old")).unwrap(),
+        meta: None,
+    };
+    let result = provider(&server, json!({}), resources)
+        .update(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(result.data.fields["title"], json!("changed"));
+}
+
+#[tokio::test]
+async fn get_many_reference_fills_the_target_template() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/comments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let rest = json!({
+        "endpoints": {"many_reference": {"path": "{target}?parentId={id}"}},
+        "pagination": "none",
+    });
+    let params = GetManyReferenceParams {
+        target: "comments".to_string(),
+        id: Identifier::Num(4),
+        pagination: PaginationPayload {
+            page: 1,
+            per_page: 5,
+        },
+        sort: SortPayload {
+            field: "id".to_string(),
+            order: SortOrder::Asc,
+        },
+        filter: FilterPayload::new(),
+        meta: None,
+    };
+    provider(&server, rest, json!([]))
+        .get_many_reference(posts(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("parentId", "4"),
+            pair("_sort", "id"),
+            pair("_order", "asc"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn composite_ids_fill_the_parts_of_the_path() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/students/ann%20lee/courses/7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(enrollment("ann lee", 7)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GetOneParams {
+        id: Identifier::Str("ann lee:7".to_string()),
+        meta: None,
+    };
+    let result = composite_provider(&server)
+        .get_one(enrollments(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(result.data.id, Identifier::Str("ann lee:7".to_string()));
+}
+
+#[tokio::test]
+async fn composite_ids_are_joined_unencoded_in_id() {
+    let server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/api/enrollments/ann:7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(enrollment("ann", 7)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = DeleteParams {
+        id: Identifier::Str("ann:7".to_string()),
+        previous_data: None,
+        meta: None,
+    };
+    composite_provider(&server)
+        .delete(enrollments(), params)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn get_many_asks_for_each_part_and_keeps_the_requested_ids() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/enrollments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            enrollment("ann", 7),
+            enrollment("ann", 8),
+            enrollment("bob", 8),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = GetManyParams {
+        ids: vec![
+            Identifier::Str("ann:7".to_string()),
+            Identifier::Str("bob:8".to_string()),
+        ],
+        meta: None,
+    };
+    let result = composite_provider(&server)
+        .get_many(enrollments(), params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&server).await,
+        vec![
+            pair("student", "ann"),
+            pair("student", "bob"),
+            pair("course", "7"),
+            pair("course", "8"),
+        ]
+    );
+    let ids: Vec<String> = result.data.iter().map(|r| r.id.to_string()).collect();
+    assert_eq!(ids, ["ann:7", "bob:8"]);
+}
+
+#[tokio::test]
+async fn composite_ids_with_missing_parts_are_rejected() {
+    let server = MockServer::start().await;
+
+    let params = GetOneParams {
+        id: Identifier::Str("ann".to_string()),
+        meta: None,
+    };
+    let Err(error) = composite_provider(&server)
+        .get_one(enrollments(), params)
+        .await
+    else {
+        panic!("expected an error");
+    };
+
+    assert!(matches!(error, Error::InvalidField { .. }));
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn update_many_patches_only_the_given_fields_by_default() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/posts/1"))
+        .and(body_json(json!({"views": 0})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(post(1, "kept")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let params = UpdateManyParams {
+        ids: vec![Identifier::Num(1)],
+        data: data(json!({"views": 0})),
+        meta: None,
+    };
+    let result = default_provider(&server)
+        .update_many(posts(), params)
+        .await
+        .unwrap();
+
+    assert!(failed_ids(&result.data).is_empty());
+}